@@ -0,0 +1,650 @@
+use crate::config::{BandConfig, Config, EnvelopeMode};
+use crate::true_gear::def::IntensityMode;
+use crate::true_gear::def::TrackObject;
+use std::collections::HashMap;
+use std::time::Duration;
+
+mod onset;
+mod resample;
+mod stft;
+
+pub(crate) use resample::ANALYSIS_RATE;
+
+/// Motor indexes of a pattern from the README, `None` if the name is unknown.
+pub(crate) fn pattern_indexes(name: &str) -> Option<Vec<i32>> {
+    let combine = |parts: &[&Vec<i32>]| parts.iter().flat_map(|p| p.iter().cloned()).collect();
+    let indexes = match name {
+        "none" => Vec::new(),
+        "middle_front" => crate::true_gear::TRUE_GEAR_SHAKE_MIDDLE_FRONT.to_vec(),
+        "middle_back" => crate::true_gear::TRUE_GEAR_SHAKE_MIDDLE_BACK.to_vec(),
+        "around_front" => crate::true_gear::TRUE_GEAR_SHAKE_AROUND_FRONT.to_vec(),
+        "around_back" => crate::true_gear::TRUE_GEAR_SHAKE_AROUND_BACK.to_vec(),
+        "up_front" => crate::true_gear::TRUE_GEAR_SHAKE_UP_FRONT.to_vec(),
+        "up_back" => crate::true_gear::TRUE_GEAR_SHAKE_UP_BACK.to_vec(),
+        "down_front" => crate::true_gear::TRUE_GEAR_SHAKE_DOWN_FRONT.to_vec(),
+        "down_back" => crate::true_gear::TRUE_GEAR_SHAKE_DOWN_BACK.to_vec(),
+        "left_front" => crate::true_gear::TRUE_GEAR_SHAKE_LEFT_FRONT.to_vec(),
+        "left_back" => crate::true_gear::TRUE_GEAR_SHAKE_LEFT_BACK.to_vec(),
+        "right_front" => crate::true_gear::TRUE_GEAR_SHAKE_RIGHT_FRONT.to_vec(),
+        "right_back" => crate::true_gear::TRUE_GEAR_SHAKE_RIGHT_BACK.to_vec(),
+        "front" => crate::true_gear::TRUE_GEAR_SHAKE_FRONT.to_vec(),
+        "back" => crate::true_gear::TRUE_GEAR_SHAKE_BACK.to_vec(),
+        "middle_all" => combine(&[
+            &crate::true_gear::TRUE_GEAR_SHAKE_MIDDLE_FRONT,
+            &crate::true_gear::TRUE_GEAR_SHAKE_MIDDLE_BACK,
+        ]),
+        "around_all" => combine(&[
+            &crate::true_gear::TRUE_GEAR_SHAKE_AROUND_FRONT,
+            &crate::true_gear::TRUE_GEAR_SHAKE_AROUND_BACK,
+        ]),
+        "up_all" => combine(&[
+            &crate::true_gear::TRUE_GEAR_SHAKE_UP_FRONT,
+            &crate::true_gear::TRUE_GEAR_SHAKE_UP_BACK,
+        ]),
+        "down_all" => combine(&[
+            &crate::true_gear::TRUE_GEAR_SHAKE_DOWN_FRONT,
+            &crate::true_gear::TRUE_GEAR_SHAKE_DOWN_BACK,
+        ]),
+        "left_all" => combine(&[
+            &crate::true_gear::TRUE_GEAR_SHAKE_LEFT_FRONT,
+            &crate::true_gear::TRUE_GEAR_SHAKE_LEFT_BACK,
+        ]),
+        "right_all" => combine(&[
+            &crate::true_gear::TRUE_GEAR_SHAKE_RIGHT_FRONT,
+            &crate::true_gear::TRUE_GEAR_SHAKE_RIGHT_BACK,
+        ]),
+        "cross_left" => combine(&[
+            &crate::true_gear::TRUE_GEAR_SHAKE_LEFT_FRONT,
+            &crate::true_gear::TRUE_GEAR_SHAKE_RIGHT_BACK,
+        ]),
+        "cross_right" => combine(&[
+            &crate::true_gear::TRUE_GEAR_SHAKE_RIGHT_FRONT,
+            &crate::true_gear::TRUE_GEAR_SHAKE_LEFT_BACK,
+        ]),
+        "all" => combine(&[
+            &crate::true_gear::TRUE_GEAR_SHAKE_MIDDLE_FRONT,
+            &crate::true_gear::TRUE_GEAR_SHAKE_MIDDLE_BACK,
+            &crate::true_gear::TRUE_GEAR_SHAKE_AROUND_FRONT,
+            &crate::true_gear::TRUE_GEAR_SHAKE_AROUND_BACK,
+        ]),
+        _ => return None,
+    };
+    Some(indexes)
+}
+
+/// Order of the channels in a surround block.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub(crate) enum ChannelOrder {
+    /// WAVE files, WASAPI and CoreAudio: FL FR C LFE, then back and side.
+    #[default]
+    Wave,
+    /// ALSA, and PulseAudio/PipeWire through it: FL FR RL RR C LFE, then side.
+    Alsa,
+}
+
+impl ChannelOrder {
+    /// The order live capture delivers on this platform.
+    pub(crate) fn native() -> ChannelOrder {
+        if cfg!(target_os = "linux") {
+            ChannelOrder::Alsa
+        } else {
+            ChannelOrder::Wave
+        }
+    }
+}
+
+/// Suit region of each channel of a 5.1 block, in the WAVE/WASAPI channel
+/// order. `None` is the LFE channel, which drives `pattern_lfe` instead.
+const SURROUND_5_1: [(&str, Option<&str>); 6] = [
+    ("FL", Some("left_front")),
+    ("FR", Some("right_front")),
+    ("C", Some("front")),
+    ("LFE", None),
+    ("SL", Some("left_back")),
+    ("SR", Some("right_back")),
+];
+/// Same as `SURROUND_5_1` for 7.1, where the back and side channels both
+/// land on the back of the suit.
+const SURROUND_7_1: [(&str, Option<&str>); 8] = [
+    ("FL", Some("left_front")),
+    ("FR", Some("right_front")),
+    ("C", Some("front")),
+    ("LFE", None),
+    ("BL", Some("left_back")),
+    ("BR", Some("right_back")),
+    ("SL", Some("left_back")),
+    ("SR", Some("right_back")),
+];
+/// `SURROUND_5_1` in the ALSA channel order.
+const SURROUND_5_1_ALSA: [(&str, Option<&str>); 6] = [
+    ("FL", Some("left_front")),
+    ("FR", Some("right_front")),
+    ("RL", Some("left_back")),
+    ("RR", Some("right_back")),
+    ("C", Some("front")),
+    ("LFE", None),
+];
+/// `SURROUND_7_1` in the ALSA channel order.
+const SURROUND_7_1_ALSA: [(&str, Option<&str>); 8] = [
+    ("FL", Some("left_front")),
+    ("FR", Some("right_front")),
+    ("RL", Some("left_back")),
+    ("RR", Some("right_back")),
+    ("C", Some("front")),
+    ("LFE", None),
+    ("SL", Some("left_back")),
+    ("SR", Some("right_back")),
+];
+
+fn pattern_to_vec(name: &str) -> Vec<i32> {
+    pattern_indexes(name).unwrap_or_else(|| {
+        eprintln!("⚠️ Unknown pattern: {}", name);
+        Vec::new()
+    })
+}
+
+/// Runs the band analysis block after block, keeping what carries over from
+/// one block to the next. Shared by live capture and file playback.
+#[derive(Default)]
+pub(crate) struct Analyzer {
+    /// Rolling peak energy per band (and channel) for `auto_gain`.
+    peaks: HashMap<String, f32>,
+    onsets: onset::OnsetDetector,
+    /// Built on the first block and rebuilt when the FFT settings change.
+    stft: Option<stft::Stft>,
+    /// Built on the first block and rebuilt when the sample rate or the
+    /// channel count changes.
+    resample: Option<resample::Resample>,
+    /// Channel count of the previous block.
+    channels: u16,
+    /// How surround channels are laid out in the blocks.
+    channel_order: ChannelOrder,
+    /// Time of the blocks too short to complete an FFT frame, added to the
+    /// next block that does.
+    unanalyzed: Duration,
+}
+
+impl Analyzer {
+    pub(crate) fn new() -> Analyzer {
+        Analyzer::default()
+    }
+
+    /// For blocks whose surround channels aren't in the WAVE order, like
+    /// live capture on Linux.
+    pub(crate) fn with_channel_order(channel_order: ChannelOrder) -> Analyzer {
+        Analyzer {
+            channel_order,
+            ..Analyzer::default()
+        }
+    }
+
+    /// Analyzes one block of interleaved samples and returns the tracks that
+    /// should be sent to the suit.
+    pub(crate) fn analyze(
+        &mut self,
+        samples: &[f32],
+        channels: u16,
+        sample_rate: u32,
+        config: &Config,
+    ) -> Vec<TrackObject> {
+        let mut true_gear_msg_vec: Vec<TrackObject> = Vec::new();
+        if samples.is_empty() {
+            return true_gear_msg_vec; // pas de data audio
+        }
+        let block = Duration::from_secs_f32(
+            samples.len() as f32 / channels.max(1) as f32 / sample_rate as f32,
+        );
+
+        let stft = match &mut self.stft {
+            Some(stft) if !stft.is_outdated(config) => stft,
+            stft => stft.insert(stft::Stft::new(config)),
+        };
+        let resample = match &mut self.resample {
+            Some(resample) if !resample.is_outdated(sample_rate, channels) => resample,
+            resample => {
+                stft.reset();
+                match resample::Resample::new(sample_rate, channels) {
+                    Ok(new) => resample.insert(new),
+                    Err(e) => {
+                        eprintln!("⚠️ Can't resample {} Hz audio: {}", sample_rate, e);
+                        return true_gear_msg_vec;
+                    }
+                }
+            }
+        };
+        if channels != self.channels {
+            stft.reset();
+            self.channels = channels;
+        }
+
+        let channel_samples = resample.process(_deinterleave(samples, channels));
+        // Each channel is analyzed on its own unless everything can be done on
+        // the downmix; stereo needs the left and right spectra. Signal 0 of
+        // the STFT is the downmix, the channels follow.
+        let channel_spectra: Vec<_> = if config.stereo || config.surround || !config.downmix {
+            channel_samples
+                .iter()
+                .enumerate()
+                .map(|(i, c)| stft.process(i + 1, c))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let spectrum = if config.downmix {
+            stft.process(0, &_downmix(&channel_samples))
+        } else {
+            _sum_spectra(&channel_spectra)
+        };
+        // No new frame: nothing to compare onsets with or to move the auto
+        // gain by, the previous shake keeps going.
+        self.unanalyzed += block;
+        if spectrum.is_empty() {
+            return true_gear_msg_vec;
+        }
+        let block = std::mem::take(&mut self.unanalyzed);
+        let onsets = if config.onset {
+            self.onsets.detect(&spectrum, block, config)
+        } else {
+            Vec::new()
+        };
+        let surround_layout: Option<&[(&str, Option<&str>)]> = match (channels, self.channel_order)
+        {
+            _ if !config.surround => None,
+            (6, ChannelOrder::Wave) => Some(&SURROUND_5_1),
+            (8, ChannelOrder::Wave) => Some(&SURROUND_7_1),
+            (6, ChannelOrder::Alsa) => Some(&SURROUND_5_1_ALSA),
+            (8, ChannelOrder::Alsa) => Some(&SURROUND_7_1_ALSA),
+            _ => None,
+        };
+        if let Some(layout) = surround_layout {
+            return self._surround_tracks(&channel_spectra, layout, &onsets, block, config);
+        }
+
+        let stereo = match channel_spectra.as_slice() {
+            [left, right, ..] if config.stereo => Some((left, right)),
+            _ => None,
+        };
+
+        for (i, band) in config.bands().iter().enumerate() {
+            let onset = onsets.get(i).copied().unwrap_or(false);
+            if let Some((left_spectrum, right_spectrum)) = stereo {
+                let left = _band_energy(left_spectrum, band) / 5.0;
+                let right = _band_energy(right_spectrum, band) / 5.0;
+                let balance = if left + right > 0.0 {
+                    ((left - right) / (left + right) * 100.0) as i32
+                } else {
+                    0
+                };
+                if config.debug {
+                    println!("DEBUG {} Balance: {:?}", band.name, balance);
+                }
+                if config.panning {
+                    let intensity = _band_energy(&spectrum, band) / 5.0;
+                    let intensity_percent = self._percent(
+                        band.name.clone(),
+                        intensity,
+                        band.default_max_intensity,
+                        block,
+                        config,
+                    );
+                    if config.debug {
+                        println!("DEBUG {} Intensity: {:?}", band.name, intensity_percent);
+                    }
+                    if let Some(strength) = _band_strength(intensity_percent, band, onset, config) {
+                        true_gear_msg_vec.extend(_panned_tracks(
+                            strength,
+                            balance as f32 / 100.0,
+                            band,
+                            config,
+                        ));
+                    }
+                    continue;
+                }
+                if balance.abs() >= config.stereo_threshold {
+                    let (side, pattern) = if balance > 0 {
+                        (left, &band.pattern_left)
+                    } else {
+                        (right, &band.pattern_right)
+                    };
+                    let side_percent = self._percent(
+                        format!("{} side", band.name),
+                        side,
+                        band.stereo_default_max_intensity,
+                        block,
+                        config,
+                    );
+                    if config.debug {
+                        println!("DEBUG {} Stereo Intensity: {:?}", band.name, side_percent);
+                    }
+                    true_gear_msg_vec.extend(_band_track(
+                        side_percent,
+                        band,
+                        pattern,
+                        onset,
+                        config,
+                    ));
+                    continue;
+                }
+            }
+
+            let intensity = _band_energy(&spectrum, band) / 5.0;
+            let intensity_percent = self._percent(
+                band.name.clone(),
+                intensity,
+                band.default_max_intensity,
+                block,
+                config,
+            );
+            if config.debug {
+                println!("DEBUG {} Intensity: {:?}", band.name, intensity_percent);
+            }
+            true_gear_msg_vec.extend(_band_track(
+                intensity_percent,
+                band,
+                &band.pattern,
+                onset,
+                config,
+            ));
+        }
+
+        true_gear_msg_vec
+    }
+
+    /// Every band of every surround channel vibrates the region of the suit the
+    /// channel points at, with the per-channel (stereo) sensitivity. The LFE
+    /// channel only feeds `pattern_lfe` through the `lfe_band`.
+    fn _surround_tracks(
+        &mut self,
+        channel_spectra: &[HashMap<u32, f32>],
+        layout: &[(&str, Option<&str>)],
+        onsets: &[bool],
+        block: Duration,
+        config: &Config,
+    ) -> Vec<TrackObject> {
+        let mut true_gear_msg_vec = Vec::new();
+        for (spectrum, &(channel, region)) in channel_spectra.iter().zip(layout) {
+            match region {
+                Some(pattern) => {
+                    for (i, band) in config.bands().iter().enumerate() {
+                        let onset = onsets.get(i).copied().unwrap_or(false);
+                        let intensity = _band_energy(spectrum, band) / 5.0;
+                        let intensity_percent = self._percent(
+                            format!("{} {}", channel, band.name),
+                            intensity,
+                            band.stereo_default_max_intensity,
+                            block,
+                            config,
+                        );
+                        if config.debug {
+                            println!(
+                                "DEBUG {} {} Intensity: {:?}",
+                                channel, band.name, intensity_percent
+                            );
+                        }
+                        true_gear_msg_vec.extend(_band_track(
+                            intensity_percent,
+                            band,
+                            pattern,
+                            onset,
+                            config,
+                        ));
+                    }
+                }
+                None => {
+                    let Some(i) = config
+                        .bands()
+                        .iter()
+                        .position(|b| b.name == config.lfe_band)
+                    else {
+                        continue;
+                    };
+                    let band = &config.bands()[i];
+                    let onset = onsets.get(i).copied().unwrap_or(false);
+                    let intensity = _band_energy(spectrum, band) / 5.0;
+                    let intensity_percent = self._percent(
+                        channel.to_string(),
+                        intensity,
+                        band.default_max_intensity,
+                        block,
+                        config,
+                    );
+                    if config.debug {
+                        println!("DEBUG {} Intensity: {:?}", channel, intensity_percent);
+                    }
+                    true_gear_msg_vec.extend(_band_track(
+                        intensity_percent,
+                        band,
+                        &config.pattern_lfe,
+                        onset,
+                        config,
+                    ));
+                }
+            }
+        }
+        true_gear_msg_vec
+    }
+
+    /// `intensity` in percent of `max_intensity`, or with `auto_gain` of the
+    /// rolling peak kept under `key`. The peak follows louder blocks with the
+    /// attack time and decays with the release time, but never drops below
+    /// `auto_gain_floor` percent of `max_intensity` so silence isn't boosted.
+    fn _percent(
+        &mut self,
+        key: String,
+        intensity: f32,
+        max_intensity: f32,
+        block: Duration,
+        config: &Config,
+    ) -> i32 {
+        if !config.auto_gain {
+            return (intensity / max_intensity * 100.0) as i32;
+        }
+        let floor = (max_intensity * config.auto_gain_floor as f32 / 100.0).max(f32::MIN_POSITIVE);
+        let peak = self.peaks.entry(key).or_insert(max_intensity);
+        let time_constant = if intensity > *peak {
+            config.auto_gain_attack
+        } else {
+            config.auto_gain_release
+        };
+        let coefficient = 1.0 - (-block.as_secs_f32() * 1000.0 / time_constant as f32).exp();
+        *peak = (*peak + (intensity - *peak) * coefficient).max(floor);
+        (intensity / *peak * 100.0) as i32
+    }
+}
+
+/// Sum of the spectrum magnitudes that fall inside the band.
+fn _band_energy(spectrum: &HashMap<u32, f32>, band: &BandConfig) -> f32 {
+    spectrum
+        .iter()
+        .filter(|(freq, _)| **freq >= band.start_freq && **freq < band.end_freq)
+        .map(|(_, value)| value)
+        .sum()
+}
+
+/// Shake strength for a band once its intensity is known, or `None` below
+/// the band's threshold or when nothing is left to send. Strength stays
+/// within `0..=intensity_max_percent`.
+fn _band_strength(
+    intensity_percent: i32,
+    band: &BandConfig,
+    onset: bool,
+    config: &Config,
+) -> Option<i32> {
+    if intensity_percent <= band.intensity_percent {
+        return None;
+    }
+    let strength = if config.onset {
+        if !onset {
+            return None;
+        }
+        config.onset_intensity
+    } else if intensity_percent > band.intensity_max_percent {
+        band.intensity_max_percent
+    } else {
+        // Goes negative for thresholds under 20%.
+        intensity_percent - 20
+    };
+    Some(strength.clamp(0, band.intensity_max_percent)).filter(|s| *s > 0)
+}
+
+/// The shake for a band once its intensity is known, see `_band_strength`.
+fn _band_track(
+    intensity_percent: i32,
+    band: &BandConfig,
+    pattern: &str,
+    onset: bool,
+    config: &Config,
+) -> Option<TrackObject> {
+    let strength = _band_strength(intensity_percent, band, onset, config)?;
+    Some(_shake(strength, band, pattern_to_vec(pattern), config))
+}
+
+/// Spreads a shake over the four motor columns of the suit following the
+/// stereo position: `pan` goes from -1 (right) to 1 (left). The loud side
+/// keeps the full strength and it fades linearly to the other side, so a
+/// centered sound shakes every column the same. One track per column.
+fn _panned_tracks(strength: i32, pan: f32, band: &BandConfig, config: &Config) -> Vec<TrackObject> {
+    let indexes = pattern_to_vec(&band.pattern);
+    (0..4)
+        .filter_map(|column| {
+            // Distance from the loud side, 0 for its outer column.
+            let distance = if pan > 0.0 { column } else { 3 - column } as f32 / 3.0;
+            let column_strength = (strength as f32 * (1.0 - pan.abs() * distance)).round() as i32;
+            let column_indexes: Vec<i32> = indexes
+                .iter()
+                .copied()
+                .filter(|index| index % 100 % 4 == column)
+                .collect();
+            if column_strength <= 0 || column_indexes.is_empty() {
+                return None;
+            }
+            Some(_shake(column_strength, band, column_indexes, config))
+        })
+        .collect()
+}
+
+/// A shake of `strength` on `index`, shaped by the band's envelope, or a
+/// short fading pulse in `onset` mode.
+fn _shake(strength: i32, band: &BandConfig, index: Vec<i32>, config: &Config) -> TrackObject {
+    if config.onset {
+        return TrackObject::new_shake_duration(
+            Some(config.onset_pulse_time as i32),
+            Some(strength),
+            Some(0),
+            Some(IntensityMode::Fade),
+            index,
+        );
+    }
+    let duration = band.envelope.duration.unwrap_or(config.vibration_time);
+    let (mode, start, end) = match band.envelope.mode {
+        EnvelopeMode::Const => (IntensityMode::Const, strength, strength),
+        EnvelopeMode::FadeOut => (IntensityMode::Fade, strength, 0),
+        EnvelopeMode::FadeIn => (IntensityMode::Fade, 0, strength),
+        EnvelopeMode::Pulse => (IntensityMode::FadeInAndOut, 0, strength),
+    };
+    TrackObject::new_shake_duration(
+        Some(duration as i32),
+        Some(start),
+        Some(end),
+        Some(mode),
+        index,
+    )
+}
+
+/// Splits an interleaved block into one sample vector per channel.
+fn _deinterleave(samples: &[f32], channels: u16) -> Vec<Vec<f32>> {
+    let channels = channels.max(1) as usize;
+    (0..channels)
+        .map(|index| {
+            samples
+                .iter()
+                .skip(index)
+                .step_by(channels)
+                .copied()
+                .collect()
+        })
+        .collect()
+}
+
+/// Averages all channels into a single mono signal.
+fn _downmix(channel_samples: &[Vec<f32>]) -> Vec<f32> {
+    let frames = channel_samples.iter().map(|c| c.len()).min().unwrap_or(0);
+    let scale = 1.0 / channel_samples.len().max(1) as f32;
+    (0..frames)
+        .map(|i| channel_samples.iter().map(|c| c[i]).sum::<f32>() * scale)
+        .collect()
+}
+
+/// Adds the magnitudes of several spectra bin by bin.
+fn _sum_spectra(spectra: &[HashMap<u32, f32>]) -> HashMap<u32, f32> {
+    let mut result = HashMap::new();
+    for spectrum in spectra {
+        for (&freq, &value) in spectrum {
+            *result.entry(freq).or_insert(0.0) += value;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _band(pattern: &str) -> BandConfig {
+        BandConfig {
+            pattern: pattern.to_string(),
+            ..BandConfig::default()
+        }
+    }
+
+    /// Column and strength of each panned track, checking that a track
+    /// stays within its column.
+    fn _columns(tracks: &[TrackObject]) -> Vec<(i32, i32)> {
+        tracks
+            .iter()
+            .map(|track| {
+                let column = track.index[0] % 100 % 4;
+                assert!(track.index.iter().all(|i| i % 100 % 4 == column));
+                let strength = track.start_intensity.max(track.end_intensity);
+                (column, strength)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn centered_sound_shakes_every_column_the_same() {
+        let tracks = _panned_tracks(60, 0.0, &_band("all"), &Config::default());
+        assert_eq!(_columns(&tracks), [(0, 60), (1, 60), (2, 60), (3, 60)]);
+    }
+
+    #[test]
+    fn panned_sound_fades_to_the_other_side() {
+        let config = Config::default();
+        let left = _panned_tracks(60, 1.0, &_band("all"), &config);
+        assert_eq!(_columns(&left), [(0, 60), (1, 40), (2, 20)]);
+        let right = _panned_tracks(60, -1.0, &_band("all"), &config);
+        assert_eq!(_columns(&right), [(1, 20), (2, 40), (3, 60)]);
+    }
+
+    #[test]
+    fn panning_keeps_the_pattern_columns() {
+        let tracks = _panned_tracks(60, 0.0, &_band("middle_all"), &Config::default());
+        assert_eq!(_columns(&tracks), [(1, 60), (2, 60)]);
+    }
+
+    #[test]
+    fn onset_strength_stays_within_the_band_max() {
+        let mut config = Config::default();
+        config.onset = true;
+        let band = BandConfig::default();
+
+        config.onset_intensity = 90;
+        assert_eq!(
+            _band_strength(50, &band, true, &config),
+            Some(band.intensity_max_percent)
+        );
+        assert_eq!(_band_strength(50, &band, false, &config), None);
+        config.onset_intensity = -10;
+        assert_eq!(_band_strength(50, &band, true, &config), None);
+    }
+}
@@ -0,0 +1,223 @@
+use crate::config::{BandConfig, Config};
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+/// How far back onsets are kept to estimate the tempo.
+const TEMPO_WINDOW: Duration = Duration::from_secs(8);
+/// Fewest onset intervals needed before a tempo is guessed.
+const TEMPO_MIN_INTERVALS: usize = 4;
+
+/// Spectral-flux onset detection: a band has an onset when the energy it
+/// gained since the previous block is well above its recent average gain.
+#[derive(Default)]
+pub(crate) struct OnsetDetector {
+    previous: HashMap<u32, f32>,
+    /// Recent flux of each band, for the adaptive threshold.
+    history: HashMap<String, VecDeque<f32>>,
+    last_onset: HashMap<String, Duration>,
+    /// Time analyzed so far.
+    clock: Duration,
+    /// Onsets of the first band, for the tempo.
+    beats: VecDeque<Duration>,
+    tempo: Option<f32>,
+}
+
+impl OnsetDetector {
+    /// Feeds one block's spectrum and tells, for each of `config.bands()`,
+    /// whether it starts a new sound.
+    pub(crate) fn detect(
+        &mut self,
+        spectrum: &HashMap<u32, f32>,
+        block: Duration,
+        config: &Config,
+    ) -> Vec<bool> {
+        self.clock += block;
+        let history_len = (config.onset_history as f32 / (block.as_secs_f32() * 1000.0))
+            .ceil()
+            .max(1.0) as usize;
+        let pulse_time = Duration::from_millis(config.onset_pulse_time.into());
+
+        let mut onsets = Vec::new();
+        for (i, band) in config.bands().iter().enumerate() {
+            let flux = _band_flux(spectrum, &self.previous, band) / 5.0;
+            let history = self.history.entry(band.name.clone()).or_default();
+            let average = history.iter().sum::<f32>() / history.len().max(1) as f32;
+            let rested = self
+                .last_onset
+                .get(&band.name)
+                .is_none_or(|last| self.clock - *last >= pulse_time);
+            let onset = !history.is_empty()
+                && flux > 0.0
+                && flux > average * config.onset_sensitivity
+                && rested;
+
+            history.push_back(flux);
+            while history.len() > history_len {
+                history.pop_front();
+            }
+            if onset {
+                self.last_onset.insert(band.name.clone(), self.clock);
+                if config.debug {
+                    println!("DEBUG {} Onset", band.name);
+                }
+                if i == 0 && config.tempo {
+                    self._beat();
+                }
+            }
+            onsets.push(onset);
+        }
+        self.previous = spectrum.clone();
+        onsets
+    }
+
+    /// Records a beat and prints the tempo when the guess changes.
+    fn _beat(&mut self) {
+        self.beats.push_back(self.clock);
+        while self
+            .beats
+            .front()
+            .is_some_and(|first| self.clock - *first > TEMPO_WINDOW)
+        {
+            self.beats.pop_front();
+        }
+        let Some(tempo) = _estimate_tempo(&self.beats) else {
+            return;
+        };
+        if self
+            .tempo
+            .is_none_or(|previous| (previous - tempo).abs() >= 2.0)
+        {
+            println!("Tempo == {:.0} BPM", tempo);
+            self.tempo = Some(tempo);
+        }
+    }
+}
+
+/// Energy the band gained since the previous spectrum, ignoring the bins
+/// that got quieter.
+fn _band_flux(
+    spectrum: &HashMap<u32, f32>,
+    previous: &HashMap<u32, f32>,
+    band: &BandConfig,
+) -> f32 {
+    spectrum
+        .iter()
+        .filter(|(freq, _)| **freq >= band.start_freq && **freq < band.end_freq)
+        .map(|(freq, value)| (value - previous.get(freq).unwrap_or(&0.0)).max(0.0))
+        .sum()
+}
+
+/// Median interval between beats, folded into 70-180 BPM so a missed or an
+/// extra beat doesn't halve or double the tempo.
+fn _estimate_tempo(beats: &VecDeque<Duration>) -> Option<f32> {
+    let mut tempos: Vec<f32> = beats
+        .iter()
+        .zip(beats.iter().skip(1))
+        .map(|(a, b)| (*b - *a).as_secs_f32())
+        .filter(|interval| *interval > 0.0)
+        .map(|interval| {
+            let mut bpm = 60.0 / interval;
+            while bpm < 70.0 {
+                bpm *= 2.0;
+            }
+            while bpm > 180.0 {
+                bpm /= 2.0;
+            }
+            bpm
+        })
+        .collect();
+    if tempos.len() < TEMPO_MIN_INTERVALS {
+        return None;
+    }
+    tempos.sort_by(|a, b| a.total_cmp(b));
+    Some(tempos[tempos.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BLOCK: Duration = Duration::from_millis(10);
+
+    /// A spectrum with `value` in one bin of the bass band.
+    fn _bass(value: f32) -> HashMap<u32, f32> {
+        HashMap::from([(50, value), (1000, 1.0), (4000, 1.0)])
+    }
+
+    /// Feeds the values one block each and returns the bass onsets.
+    fn _bass_onsets(values: impl IntoIterator<Item = f32>) -> Vec<bool> {
+        let mut config = Config::default();
+        config.onset = true;
+        let mut detector = OnsetDetector::default();
+        values
+            .into_iter()
+            .map(|value| detector.detect(&_bass(value), BLOCK, &config)[0])
+            .collect()
+    }
+
+    fn _beats(intervals_ms: &[u64]) -> VecDeque<Duration> {
+        let mut clock = Duration::ZERO;
+        let mut beats = VecDeque::from([clock]);
+        for interval in intervals_ms {
+            clock += Duration::from_millis(*interval);
+            beats.push_back(clock);
+        }
+        beats
+    }
+
+    fn _assert_bpm(intervals_ms: &[u64], expected: f32) {
+        let tempo = _estimate_tempo(&_beats(intervals_ms));
+        assert!(
+            tempo.is_some_and(|bpm| (bpm - expected).abs() < 0.01),
+            "{:?} BPM instead of {}",
+            tempo,
+            expected
+        );
+    }
+
+    #[test]
+    fn a_step_in_energy_is_one_onset() {
+        let values = [1.0; 50].into_iter().chain([10.0; 50]);
+        let onsets = _bass_onsets(values);
+        assert_eq!(onsets.iter().filter(|o| **o).count(), 1);
+        assert!(onsets[50]);
+    }
+
+    #[test]
+    fn a_steady_drone_has_no_onset() {
+        assert!(_bass_onsets([5.0; 100]).iter().all(|o| !o));
+    }
+
+    #[test]
+    fn no_retrigger_within_the_pulse_time() {
+        // Rises every 20 ms, the default pulse time is 80 ms.
+        let values = (0..100).map(|i| if i % 2 == 0 { 1.0 } else { 10.0 });
+        let onsets: Vec<usize> = _bass_onsets(values)
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| **o)
+            .map(|(i, _)| i)
+            .collect();
+        assert!(onsets.len() > 1);
+        for pair in onsets.windows(2) {
+            assert!(pair[1] - pair[0] >= 8, "onsets at {:?}", onsets);
+        }
+    }
+
+    #[test]
+    fn tempo_is_folded_into_70_to_180_bpm() {
+        _assert_bpm(&[500; 4], 120.0);
+        // 30 and 240 BPM are 120 BPM with missed or extra beats.
+        _assert_bpm(&[2000; 4], 120.0);
+        _assert_bpm(&[250; 4], 120.0);
+        // 200 BPM halves to 100.
+        _assert_bpm(&[300; 4], 100.0);
+        // The median ignores one odd interval.
+        _assert_bpm(&[500, 500, 730, 500], 120.0);
+    }
+
+    #[test]
+    fn tempo_needs_enough_beats() {
+        assert_eq!(_estimate_tempo(&_beats(&[500; 3])), None);
+    }
+}
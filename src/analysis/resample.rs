@@ -0,0 +1,154 @@
+use rubato::{FftFixedIn, Resampler};
+
+/// Rate everything is analyzed at, whatever the device or file runs at, so
+/// the FFT bins, band edges and sensitivities mean the same on every device.
+pub(crate) const ANALYSIS_RATE: u32 = 48_000;
+/// Input frames the resampler converts at a time.
+const CHUNK_FRAMES: usize = 1024;
+
+/// Streaming conversion of deinterleaved channels to `ANALYSIS_RATE`.
+/// Samples are buffered until a whole chunk is available, so a block may
+/// come out a bit shorter or longer than it went in.
+pub(crate) struct Resample {
+    rate: u32,
+    /// `None` when the input already runs at `ANALYSIS_RATE`.
+    resampler: Option<FftFixedIn<f32>>,
+    /// Samples not resampled yet, per channel.
+    pending: Vec<Vec<f32>>,
+}
+
+impl Resample {
+    pub(crate) fn new(rate: u32, channels: u16) -> anyhow::Result<Resample> {
+        let resampler = if rate == ANALYSIS_RATE {
+            None
+        } else {
+            Some(FftFixedIn::new(
+                rate as usize,
+                ANALYSIS_RATE as usize,
+                CHUNK_FRAMES,
+                2,
+                channels as usize,
+            )?)
+        };
+        Ok(Resample {
+            rate,
+            resampler,
+            pending: vec![Vec::new(); channels as usize],
+        })
+    }
+
+    /// Whether it was built for another sample rate or channel count.
+    pub(crate) fn is_outdated(&self, rate: u32, channels: u16) -> bool {
+        self.rate != rate || self.pending.len() != channels as usize
+    }
+
+    /// Adds a block and returns what could be resampled so far.
+    pub(crate) fn process(&mut self, channels: Vec<Vec<f32>>) -> Vec<Vec<f32>> {
+        let Some(resampler) = &mut self.resampler else {
+            return channels;
+        };
+        for (pending, samples) in self.pending.iter_mut().zip(&channels) {
+            pending.extend_from_slice(samples);
+        }
+
+        let mut output = vec![Vec::new(); self.pending.len()];
+        let mut start = 0;
+        loop {
+            let frames = resampler.input_frames_next();
+            if self.pending.first().map_or(0, Vec::len) < start + frames {
+                break;
+            }
+            let chunk: Vec<&[f32]> = self
+                .pending
+                .iter()
+                .map(|p| &p[start..start + frames])
+                .collect();
+            match resampler.process(&chunk, None) {
+                Ok(resampled) => {
+                    for (output, resampled) in output.iter_mut().zip(resampled) {
+                        output.extend(resampled);
+                    }
+                }
+                Err(e) => eprintln!("⚠️ Failed to resample the audio: {}", e),
+            }
+            start += frames;
+        }
+        for pending in &mut self.pending {
+            pending.drain(..start);
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::stft::Stft;
+    use crate::config::Config;
+    use std::f32::consts::PI;
+
+    /// One second of a `freq` Hz sine at `rate`, in blocks of `block` frames.
+    fn _sine_blocks(freq: f32, rate: u32, block: usize) -> Vec<Vec<f32>> {
+        let samples: Vec<f32> = (0..rate)
+            .map(|i| 0.5 * (2.0 * PI * freq * i as f32 / rate as f32).sin())
+            .collect();
+        samples.chunks(block).map(|c| c.to_vec()).collect()
+    }
+
+    #[test]
+    fn output_length_follows_the_rate_ratio() {
+        for (rate, block) in [
+            (44_100, 2205),
+            (44_100, 1000),
+            (96_000, 777),
+            (192_000, 9600),
+        ] {
+            let mut resample = Resample::new(rate, 2).unwrap();
+            let mut frames = 0;
+            for samples in _sine_blocks(440.0, rate, block) {
+                let output = resample.process(vec![samples.clone(), samples]);
+                assert_eq!(output[0].len(), output[1].len());
+                frames += output[0].len();
+            }
+            // What is still buffered is less than a chunk of input.
+            let missing = (CHUNK_FRAMES as u32 * ANALYSIS_RATE / rate) as usize + 1;
+            assert!(
+                frames <= ANALYSIS_RATE as usize && frames + missing >= ANALYSIS_RATE as usize,
+                "{} Hz in blocks of {}: {} frames out",
+                rate,
+                block,
+                frames
+            );
+        }
+    }
+
+    #[test]
+    fn a_sine_has_the_same_band_energy_at_any_rate() {
+        let config = Config::default();
+        let energies: Vec<f32> = [44_100, 48_000, 96_000]
+            .into_iter()
+            .map(|rate| {
+                let mut resample = Resample::new(rate, 1).unwrap();
+                let mut stft = Stft::new(&config);
+                let mut energy = 0.0;
+                for samples in _sine_blocks(1000.0, rate, rate as usize / 20) {
+                    let output = resample.process(vec![samples]);
+                    let spectrum = stft.process(0, &output[0]);
+                    if !spectrum.is_empty() {
+                        energy = spectrum
+                            .iter()
+                            .filter(|(freq, _)| (900..1100).contains(*freq))
+                            .map(|(_, value)| value)
+                            .sum();
+                    }
+                }
+                energy
+            })
+            .collect();
+        assert!(energies[1] > 0.0);
+        for energy in &energies {
+            let error = (energy - energies[1]).abs() / energies[1];
+            assert!(error < 0.02, "band energies differ: {:?}", energies);
+        }
+    }
+}
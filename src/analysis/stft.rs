@@ -0,0 +1,172 @@
+use super::resample::ANALYSIS_RATE;
+use crate::config::{Config, WindowFunction};
+use rustfft::num_complex::Complex;
+use rustfft::{Fft, FftPlanner};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+/// Streaming short-time FFT over several signals at once (the downmix and
+/// each channel). Samples are buffered between blocks so every frame has
+/// the same size, frames overlap by `fft_size - fft_hop`, and the plan and
+/// window are only rebuilt when the config changes.
+pub(crate) struct Stft {
+    size: usize,
+    hop: usize,
+    window_function: WindowFunction,
+    fft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    /// Makes the window's loss of energy up so the sensitivities don't
+    /// depend on the window.
+    window_gain: f32,
+    /// Samples not consumed yet, per signal.
+    pending: Vec<Vec<f32>>,
+}
+
+impl Stft {
+    pub(crate) fn new(config: &Config) -> Stft {
+        let size = config.fft_size;
+        let window = _window(config.fft_window, size);
+        let window_gain = size as f32 / window.iter().sum::<f32>();
+        Stft {
+            size,
+            hop: config.fft_hop,
+            window_function: config.fft_window,
+            fft: FftPlanner::new().plan_fft_forward(size),
+            window,
+            window_gain,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Whether the STFT was built for another size, hop or window.
+    pub(crate) fn is_outdated(&self, config: &Config) -> bool {
+        self.size != config.fft_size
+            || self.hop != config.fft_hop
+            || self.window_function != config.fft_window
+    }
+
+    /// Adds a block of `signal`, at `ANALYSIS_RATE`, and returns the
+    /// magnitudes of every frame it completed, averaged, by frequency. Empty
+    /// while less than a frame has been buffered.
+    pub(crate) fn process(&mut self, signal: usize, samples: &[f32]) -> HashMap<u32, f32> {
+        if self.pending.len() <= signal {
+            self.pending.resize(signal + 1, Vec::new());
+        }
+        let pending = &mut self.pending[signal];
+        pending.extend_from_slice(samples);
+
+        let mut magnitudes = vec![0.0; self.size / 2 + 1];
+        let mut frames = 0;
+        let mut start = 0;
+        let mut buffer = vec![Complex { re: 0.0, im: 0.0 }; self.size];
+        while start + self.size <= pending.len() {
+            for (i, value) in buffer.iter_mut().enumerate() {
+                *value = Complex {
+                    re: pending[start + i] * self.window[i],
+                    im: 0.0,
+                };
+            }
+            self.fft.process(&mut buffer);
+            // Bins past Nyquist only mirror the first half for a real signal.
+            for (magnitude, c) in magnitudes.iter_mut().zip(&buffer) {
+                *magnitude += c.norm();
+            }
+            frames += 1;
+            start += self.hop;
+        }
+        pending.drain(..start.min(pending.len()));
+
+        let mut result = HashMap::new();
+        if frames == 0 {
+            return result;
+        }
+        let scale = self.window_gain / frames as f32;
+        for (i, magnitude) in magnitudes.into_iter().enumerate() {
+            let freq = (i as f32 * ANALYSIS_RATE as f32 / self.size as f32) as u32;
+            *result.entry(freq).or_insert(0.0) += magnitude * scale;
+        }
+        result
+    }
+
+    /// Drops the buffered samples, e.g. when the channel count changes.
+    pub(crate) fn reset(&mut self) {
+        self.pending.clear();
+    }
+}
+
+fn _window(function: WindowFunction, size: usize) -> Vec<f32> {
+    let phase = |i: usize| 2.0 * PI * i as f32 / size as f32;
+    (0..size)
+        .map(|i| match function {
+            WindowFunction::Rectangular => 1.0,
+            WindowFunction::Hann => 0.5 - 0.5 * phase(i).cos(),
+            WindowFunction::Blackman => 0.42 - 0.5 * phase(i).cos() + 0.08 * (2.0 * phase(i)).cos(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _config(size: usize, hop: usize, window: WindowFunction) -> Config {
+        let mut config = Config::default();
+        config.fft_size = size;
+        config.fft_hop = hop;
+        config.fft_window = window;
+        config
+    }
+
+    /// `len` samples of a sine `amplitude` high on the center of FFT bin `bin`.
+    fn _sine(bin: usize, size: usize, amplitude: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| amplitude * (2.0 * PI * bin as f32 * i as f32 / size as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn a_short_block_carries_over_to_the_next() {
+        let samples = _sine(40, 2048, 0.5, 2048);
+        let mut stft = Stft::new(&_config(2048, 2048, WindowFunction::Hann));
+        assert!(stft.process(0, &samples[..1000]).is_empty());
+        let split = stft.process(0, &samples[1000..]);
+
+        let mut whole = Stft::new(&_config(2048, 2048, WindowFunction::Hann));
+        assert_eq!(split, whole.process(0, &samples));
+    }
+
+    #[test]
+    fn overlapping_frames_advance_by_the_hop() {
+        let mut stft = Stft::new(&_config(2048, 512, WindowFunction::Hann));
+        stft.process(0, &vec![0.1; 4096]);
+        // (4096 - 2048) / 512 + 1 = 5 frames, the next one starts at 2560.
+        assert_eq!(stft.pending[0].len(), 4096 - 5 * 512);
+    }
+
+    #[test]
+    fn a_sine_lands_in_its_bin_whatever_the_window() {
+        let size = 2048;
+        let bin = 40;
+        let freq = (bin as f32 * ANALYSIS_RATE as f32 / size as f32) as u32;
+        for (name, window) in [
+            ("rectangular", WindowFunction::Rectangular),
+            ("hann", WindowFunction::Hann),
+            ("blackman", WindowFunction::Blackman),
+        ] {
+            let mut stft = Stft::new(&_config(size, size, window));
+            let spectrum = stft.process(0, &_sine(bin, size, 0.5, size));
+            let (&peak, &magnitude) = spectrum.iter().max_by(|a, b| a.1.total_cmp(b.1)).unwrap();
+            assert_eq!(peak, freq, "{}", name);
+            // A sine of amplitude A gives A * size / 2 in its bin.
+            let expected = 0.5 * size as f32 / 2.0;
+            assert!(
+                (magnitude - expected).abs() / expected < 0.01,
+                "{}: {} instead of {}",
+                name,
+                magnitude,
+                expected
+            );
+        }
+    }
+}
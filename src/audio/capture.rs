@@ -0,0 +1,294 @@
+use crate::audio::Source;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use cpal::{FromSample, Sample, SizedSample};
+use ringbuf::traits::{Consumer, Observer, Producer, Split};
+use ringbuf::{HeapCons, HeapProd, HeapRb};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+/// How much captured audio the ring buffer holds before samples are dropped.
+const CAPTURE_BUFFER: Duration = Duration::from_secs(2);
+/// How often the supervisor checks the stream and the default device.
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Wait before trying again when the device can't be opened.
+const REOPEN_DELAY: Duration = Duration::from_secs(2);
+
+/// One opened stream: where its samples arrive and their layout.
+struct CaptureStream {
+    consumer: HeapCons<f32>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+/// Live audio capture that survives the device going away. A supervisor
+/// thread owns the cpal stream (it can't move between threads on every
+/// backend) and rebuilds it when it reports an error or, when no device was
+/// picked, when the default device changes; the new ring is then handed to
+/// `read`.
+pub(crate) struct Capture {
+    streams: mpsc::Receiver<CaptureStream>,
+    current: Option<CaptureStream>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Capture {
+    /// Opens the device like `audio::init` and starts supervising it. Fails
+    /// if the device can't be opened the first time.
+    pub(crate) fn start(selector: Option<String>, source: Source) -> anyhow::Result<Capture> {
+        let (streams_tx, streams) = mpsc::channel();
+        let (ready_tx, ready) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        {
+            let stop = stop.clone();
+            thread::Builder::new()
+                .name("audio-capture".to_string())
+                .spawn(move || _supervise(selector, source, streams_tx, ready_tx, stop))?;
+        }
+        ready
+            .recv()
+            .map_err(|_| anyhow::anyhow!("Audio capture thread stopped"))??;
+        Ok(Capture {
+            streams,
+            current: None,
+            stop,
+        })
+    }
+
+    /// The whole frames captured since the last call, with their channel
+    /// count and sample rate. Switches to the newest stream after a rebuild.
+    pub(crate) fn read(&mut self) -> Option<(Vec<f32>, u16, u32)> {
+        while let Ok(stream) = self.streams.try_recv() {
+            self.current = Some(stream);
+        }
+        let current = self.current.as_mut()?;
+        let channels = current.channels as usize;
+        // Whole frames only, so the channels stay aligned.
+        let len = current.consumer.occupied_len() / channels * channels;
+        if len == 0 {
+            return None;
+        }
+        let samples = current.consumer.pop_iter().take(len).collect();
+        Some((samples, current.channels, current.sample_rate))
+    }
+}
+
+impl Drop for Capture {
+    /// Tells the supervisor to close the stream at its next check, without
+    /// waiting for it.
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn _supervise(
+    selector: Option<String>,
+    source: Source,
+    streams: mpsc::Sender<CaptureStream>,
+    ready: mpsc::Sender<anyhow::Result<()>>,
+    stop: Arc<AtomicBool>,
+) {
+    let mut ready = Some(ready);
+    while !stop.load(Ordering::Relaxed) {
+        let default = _default_device(source);
+        let (stream, capture, failed) = match _open(selector.as_deref(), source) {
+            Ok(opened) => opened,
+            Err(e) => {
+                if let Some(ready) = ready.take() {
+                    let _ = ready.send(Err(e));
+                    return;
+                }
+                eprintln!("⚠️ {}, trying again", e);
+                thread::sleep(REOPEN_DELAY);
+                continue;
+            }
+        };
+        if let Some(ready) = ready.take() {
+            let _ = ready.send(Ok(()));
+        }
+        if streams.send(capture).is_err() {
+            return;
+        }
+
+        loop {
+            thread::sleep(DEVICE_POLL_INTERVAL);
+            if stop.load(Ordering::Relaxed) {
+                return;
+            }
+            if failed.load(Ordering::Relaxed) {
+                eprintln!("⚠️ Audio stream failed, reopening the device");
+                break;
+            }
+            if selector.is_none() && _default_device(source) != default {
+                println!("Default audio device changed, switching to it");
+                break;
+            }
+        }
+        drop(stream);
+    }
+}
+
+/// Something that changes when the default device of `source` does.
+fn _default_device(source: Source) -> Option<String> {
+    use cpal::traits::HostTrait;
+    #[cfg(target_os = "linux")]
+    if source == Source::Output {
+        if let Some(sink) = super::_default_sink() {
+            return Some(sink);
+        }
+    }
+    let host = cpal::default_host();
+    let device = match source {
+        Source::Output => host.default_output_device(),
+        Source::Input => host.default_input_device(),
+    };
+    device.and_then(|d| d.name().ok())
+}
+
+/// Opens and starts a stream, with the flag its error callback raises.
+fn _open(
+    selector: Option<&str>,
+    source: Source,
+) -> anyhow::Result<(cpal::Stream, CaptureStream, Arc<AtomicBool>)> {
+    let (audio_device, audio_config) = super::init(selector, source)?;
+    let channels = audio_config.channels();
+    let sample_rate = audio_config.sample_rate().0;
+    println!(
+        "Audio config == {} channels, {} Hz, {}",
+        channels,
+        sample_rate,
+        audio_config.sample_format()
+    );
+    let capacity = (CAPTURE_BUFFER.as_secs_f32() * sample_rate as f32) as usize * channels as usize;
+    let (producer, consumer) = HeapRb::<f32>::new(capacity).split();
+    let failed = Arc::new(AtomicBool::new(false));
+
+    let stream_config = audio_config.config();
+    let device = &audio_device;
+    let f = failed.clone();
+    let stream = match audio_config.sample_format() {
+        cpal::SampleFormat::I8 => build_input_stream::<i8>(device, &stream_config, producer, f)?,
+        cpal::SampleFormat::I16 => build_input_stream::<i16>(device, &stream_config, producer, f)?,
+        cpal::SampleFormat::I32 => build_input_stream::<i32>(device, &stream_config, producer, f)?,
+        cpal::SampleFormat::I64 => build_input_stream::<i64>(device, &stream_config, producer, f)?,
+        cpal::SampleFormat::U8 => build_input_stream::<u8>(device, &stream_config, producer, f)?,
+        cpal::SampleFormat::U16 => build_input_stream::<u16>(device, &stream_config, producer, f)?,
+        cpal::SampleFormat::U32 => build_input_stream::<u32>(device, &stream_config, producer, f)?,
+        cpal::SampleFormat::U64 => build_input_stream::<u64>(device, &stream_config, producer, f)?,
+        cpal::SampleFormat::F32 => build_input_stream::<f32>(device, &stream_config, producer, f)?,
+        cpal::SampleFormat::F64 => build_input_stream::<f64>(device, &stream_config, producer, f)?,
+        sample_format => {
+            return Err(anyhow::Error::msg(format!(
+                "Unsupported sample format '{sample_format}'"
+            )))
+        }
+    };
+    stream.play()?;
+
+    let capture = CaptureStream {
+        consumer,
+        channels,
+        sample_rate,
+    };
+    Ok((stream, capture, failed))
+}
+
+/// Opens the capture stream for devices delivering `T` samples; they are
+/// converted to f32 in the callback. Errors raise `failed` so the
+/// supervisor rebuilds the stream.
+fn build_input_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    mut producer: HeapProd<f32>,
+    failed: Arc<AtomicBool>,
+) -> anyhow::Result<cpal::Stream>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let stream = device.build_input_stream(
+        config,
+        move |data: &[T], _: &_| write_input_data(data, &mut producer),
+        move |err| {
+            eprintln!("an error occurred on stream: {}", err);
+            failed.store(true, Ordering::Relaxed);
+        },
+        None,
+    )?;
+    Ok(stream)
+}
+
+/// Runs on the audio thread: converts the samples to f32 and pushes them
+/// into the ring without locking or allocating. A callback that doesn't fit
+/// is dropped whole so the channels stay aligned.
+fn write_input_data<T>(input: &[T], producer: &mut HeapProd<f32>)
+where
+    T: Sample,
+    f32: FromSample<T>,
+{
+    if producer.vacant_len() < input.len() {
+        return;
+    }
+    for &sample in input {
+        let _ = producer.try_push(f32::from_sample(sample));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pushes `input` through a ring like the audio callback and reads it back.
+    fn _convert<T>(input: &[T]) -> Vec<f32>
+    where
+        T: Sample,
+        f32: FromSample<T>,
+    {
+        let (mut producer, mut consumer) = HeapRb::<f32>::new(16).split();
+        write_input_data(input, &mut producer);
+        consumer.pop_iter().collect()
+    }
+
+    /// Min, zero and max of a format with `bits` bits.
+    fn _expected(bits: i32) -> [f32; 3] {
+        [-1.0, 0.0, 1.0 - 2f32.powi(1 - bits)]
+    }
+
+    fn _assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn converts_signed_formats() {
+        _assert_close(&_convert(&[i8::MIN, 0, i8::MAX]), &_expected(8));
+        _assert_close(&_convert(&[i16::MIN, 0, i16::MAX]), &_expected(16));
+        _assert_close(&_convert(&[i32::MIN, 0, i32::MAX]), &_expected(32));
+        _assert_close(&_convert(&[i64::MIN, 0, i64::MAX]), &_expected(64));
+    }
+
+    #[test]
+    fn converts_unsigned_formats() {
+        _assert_close(&_convert(&[u8::MIN, 1 << 7, u8::MAX]), &_expected(8));
+        _assert_close(&_convert(&[u16::MIN, 1 << 15, u16::MAX]), &_expected(16));
+        _assert_close(&_convert(&[u32::MIN, 1 << 31, u32::MAX]), &_expected(32));
+        _assert_close(&_convert(&[u64::MIN, 1 << 63, u64::MAX]), &_expected(64));
+    }
+
+    #[test]
+    fn converts_float_formats() {
+        _assert_close(&_convert(&[-1.0f32, 0.0, 1.0]), &[-1.0, 0.0, 1.0]);
+        _assert_close(&_convert(&[-1.0f64, 0.0, 1.0]), &[-1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn drops_a_callback_that_does_not_fit_whole() {
+        let (mut producer, mut consumer) = HeapRb::<f32>::new(4).split();
+        write_input_data(&[0.5f32, -0.5], &mut producer);
+        write_input_data(&[0.25f32, 0.25, 0.25], &mut producer);
+        assert_eq!(consumer.pop_iter().collect::<Vec<_>>(), vec![0.5, -0.5]);
+    }
+}
@@ -1,12 +1,222 @@
 use cpal::traits::{DeviceTrait, HostTrait};
+use std::fmt;
 
-pub fn init() -> anyhow::Result<cpal::Device> {
+pub(crate) mod capture;
+
+/// An audio device of one of the cpal hosts, numbered across all hosts.
+pub(crate) struct AudioDevice {
+    pub index: usize,
+    pub host: cpal::HostId,
+    pub name: String,
+    pub input: bool,
+    pub output: bool,
+    pub device: cpal::Device,
+}
+
+impl fmt::Display for AudioDevice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match (self.input, self.output) {
+            (true, true) => "input/output",
+            (true, false) => "input",
+            (false, true) => "output",
+            (false, false) => "unusable",
+        };
+        write!(
+            f,
+            "{:>3}: {} [{} {}]",
+            self.index,
+            self.name,
+            self.host.name(),
+            kind
+        )
+    }
+}
+
+/// Every input and output device of every available host.
+pub(crate) fn list() -> anyhow::Result<Vec<AudioDevice>> {
+    let mut devices = Vec::new();
+    for host_id in cpal::available_hosts() {
+        let host = match cpal::host_from_id(host_id) {
+            Ok(host) => host,
+            Err(e) => {
+                eprintln!("⚠️ Audio host {} unavailable: {}", host_id.name(), e);
+                continue;
+            }
+        };
+        for device in host.devices()? {
+            let name = device.name().unwrap_or_else(|_| "<unknown>".to_string());
+            let input = device
+                .supported_input_configs()
+                .is_ok_and(|mut c| c.next().is_some());
+            let output = device
+                .supported_output_configs()
+                .is_ok_and(|mut c| c.next().is_some());
+            devices.push(AudioDevice {
+                index: devices.len(),
+                host: host_id,
+                name,
+                input,
+                output,
+                device,
+            });
+        }
+    }
+    Ok(devices)
+}
+
+/// What the capture listens to.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Source {
+    /// The sound the PC plays, through loopback or a monitor source.
+    Output,
+    /// A microphone, line-in or audio interface.
+    Input,
+}
+
+/// Opens the device to capture with the config to capture it with: the
+/// default device of `source` (the monitor of the default sink on Linux for
+/// `Output`), or the one `selector` names by index, exact name or part of
+/// its name. With `Input` only input devices can be selected.
+pub fn init(
+    selector: Option<&str>,
+    source: Source,
+) -> anyhow::Result<(cpal::Device, cpal::SupportedStreamConfig)> {
+    let Some(selector) = selector else {
+        if source == Source::Input {
+            let device = cpal::default_host()
+                .default_input_device()
+                .ok_or_else(|| anyhow::anyhow!("No input device available"))?;
+            println!("Default input device == {:?}", device.name()?);
+            let config = device.default_input_config()?;
+            return Ok((device, config));
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(device) = _linux_monitor() {
+            let config = device.default_input_config()?;
+            return Ok((device, config));
+        }
+        let host = cpal::default_host();
+        let device = host.default_output_device();
+        if device.is_none() {
+            return Err(anyhow::anyhow!("No output device available"));
+        }
+        let device = device.unwrap();
+        println!("Default audio device == {:?}", device.name()?);
+        let config = device.default_output_config()?;
+        return Ok((device, config));
+    };
+
+    let mut devices = list()?;
+    if source == Source::Input {
+        devices.retain(|d| d.input);
+    }
+    let device = _select(&devices, selector).map_err(|e| {
+        if devices.is_empty() {
+            return anyhow::anyhow!("{}, no audio device found", e);
+        }
+        let available: Vec<String> = devices.iter().map(|d| d.to_string()).collect();
+        anyhow::anyhow!("{}. Available devices:\n{}", e, available.join("\n"))
+    })?;
+    println!("Audio device == {}", device);
+    // Input devices are recorded directly, output-only ones through loopback.
+    let config = if device.input {
+        device.device.default_input_config()?
+    } else {
+        device.device.default_output_config()?
+    };
+    Ok((device.device.clone(), config))
+}
+
+/// PulseAudio and PipeWire name the monitor of the current default sink.
+#[cfg(target_os = "linux")]
+const DEFAULT_MONITOR: &str = "@DEFAULT_MONITOR@";
+
+/// Points the ALSA `pulse` device, which records from `PULSE_SOURCE`, at the
+/// monitor of the default sink unless the user set it. The server resolves
+/// the name on every connection, so a reopened stream follows a new default
+/// sink. Setting the environment isn't thread safe: call it before any
+/// thread starts.
+#[cfg(target_os = "linux")]
+pub(crate) fn init_monitor_source() {
+    if std::env::var_os("PULSE_SOURCE").is_none() {
+        std::env::set_var("PULSE_SOURCE", DEFAULT_MONITOR);
+    }
+}
+
+/// On PulseAudio/PipeWire the sound sent to the speakers can be recorded
+/// from the `.monitor` source of the default sink, through the ALSA `pulse`
+/// device once `init_monitor_source` pointed it there. Falls back to any
+/// input device with "monitor" in its name, or `None` to let the caller try
+/// the output device.
+#[cfg(target_os = "linux")]
+fn _linux_monitor() -> Option<cpal::Device> {
     let host = cpal::default_host();
-    let device = host.default_output_device();
-    if device.is_none() {
-        return Err(anyhow::anyhow!("No output device available"));
+    let inputs: Vec<cpal::Device> = match host.input_devices() {
+        Ok(devices) => devices.collect(),
+        Err(e) => {
+            eprintln!("⚠️ Failed to list the input devices: {}", e);
+            return None;
+        }
+    };
+    let named = |name: &str| {
+        inputs
+            .iter()
+            .find(|d| d.name().is_ok_and(|n| n == name))
+            .cloned()
+    };
+
+    // The server resolves `PULSE_SOURCE` itself, `pactl` only names it.
+    if let Ok(source) = std::env::var("PULSE_SOURCE") {
+        if let Some(device) = named("pulse").or_else(|| named("pipewire")) {
+            let source = match _default_sink() {
+                Some(sink) if source == DEFAULT_MONITOR => format!("{}.monitor", sink),
+                _ => source,
+            };
+            println!("Audio device == {} (through the pulse device)", source);
+            return Some(device);
+        }
+    }
+
+    let monitor = inputs
+        .into_iter()
+        .find(|d| d.name().is_ok_and(|n| n.to_lowercase().contains("monitor")))?;
+    println!("Audio device == {:?}", monitor.name().ok()?);
+    Some(monitor)
+}
+
+/// Name of the default PulseAudio/PipeWire sink, from `pactl`.
+#[cfg(target_os = "linux")]
+pub(super) fn _default_sink() -> Option<String> {
+    let output = std::process::Command::new("pactl")
+        .arg("get-default-sink")
+        .output()
+        .ok()?;
+    let sink = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (output.status.success() && !sink.is_empty()).then_some(sink)
+}
+
+fn _select<'a>(devices: &'a [AudioDevice], selector: &str) -> anyhow::Result<&'a AudioDevice> {
+    if let Ok(index) = selector.parse::<usize>() {
+        return devices
+            .iter()
+            .find(|d| d.index == index)
+            .ok_or_else(|| anyhow::anyhow!("There is no audio device {}", index));
+    }
+    if let Some(device) = devices.iter().find(|d| d.name == selector) {
+        return Ok(device);
+    }
+    let lowercase = selector.to_lowercase();
+    let matches: Vec<&AudioDevice> = devices
+        .iter()
+        .filter(|d| d.name.to_lowercase().contains(&lowercase))
+        .collect();
+    match matches.as_slice() {
+        [device] => Ok(device),
+        [] => Err(anyhow::anyhow!("No audio device matches '{}'", selector)),
+        _ => Err(anyhow::anyhow!(
+            "'{}' matches {} audio devices, use a longer name or the index",
+            selector,
+            matches.len()
+        )),
     }
-    let device = device.unwrap();
-    println!("Default audio device == {:?}", device.name()?);
-    Ok(device)
 }
@@ -0,0 +1,121 @@
+use std::path::PathBuf;
+
+const USAGE: &str = "Usage:
+  truegear_audio_driver                  capture the default output device
+  truegear_audio_driver input           capture a microphone or line-in instead
+  truegear_audio_driver devices          list the audio devices --device can pick
+  truegear_audio_driver play <file.wav>  drive the suit from a WAV file
+  truegear_audio_driver render <file.wav> [out.jsonl]
+                                         export the haptic timeline of a WAV file
+  truegear_audio_driver replay <timeline.jsonl>
+                                         send a recorded timeline to the suit
+  truegear_audio_driver mock-server [addr]
+                                         stand in for the TrueGear app (default 127.0.0.1:18233)
+
+Options:
+  --server <url>                         TrueGear app URL (default ws://localhost:18233/v1/tact/)
+  --device <name|index>                  audio device to capture, by name, part of it or index
+                                         (only input devices with `input`)";
+
+pub(crate) enum Command {
+    /// Capture the default output device and drive the suit live.
+    Capture,
+    /// Capture an input device (microphone, line-in) and drive the suit live.
+    Input,
+    /// List the audio devices of every host.
+    Devices,
+    /// Analyze a WAV file at real-time pace and drive the suit from it.
+    Play { path: PathBuf },
+    /// Analyze a whole WAV file as fast as possible and write the resulting
+    /// tracks as a JSON lines timeline instead of sending them.
+    Render { input: PathBuf, output: PathBuf },
+    /// Send a recorded or hand-written timeline to the suit at its offsets.
+    Replay { path: PathBuf },
+    /// Run a local stand-in for the TrueGear app's WebSocket server.
+    MockServer { addr: String },
+}
+
+pub(crate) struct Args {
+    pub command: Command,
+    /// `--server`, overrides `server_url` from the config.
+    pub server: Option<String>,
+    /// `--device`, overrides `device` from the config.
+    pub device: Option<String>,
+}
+
+pub(crate) fn parse() -> anyhow::Result<Args> {
+    let mut server = None;
+    let mut device = None;
+    let mut positional = Vec::new();
+    let mut raw = std::env::args().skip(1);
+    while let Some(arg) = raw.next() {
+        if let Some(value) = _option_value("--server", &arg, &mut raw)? {
+            server = Some(value);
+        } else if let Some(value) = _option_value("--device", &arg, &mut raw)? {
+            device = Some(value);
+        } else if arg.starts_with("--") {
+            return Err(anyhow::anyhow!("Unknown option '{}'\n{}", arg, USAGE));
+        } else {
+            positional.push(arg);
+        }
+    }
+
+    let mut args = positional.into_iter();
+    let command = match args.next().as_deref() {
+        None | Some("capture") => Command::Capture,
+        Some("input") => Command::Input,
+        Some("devices") => Command::Devices,
+        Some("play") => {
+            let path = args
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("Missing WAV file path\n{}", USAGE))?;
+            Command::Play { path: path.into() }
+        }
+        Some("render") => {
+            let input: PathBuf = args
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("Missing WAV file path\n{}", USAGE))?
+                .into();
+            let output = args
+                .next()
+                .map(PathBuf::from)
+                .unwrap_or_else(|| input.with_extension("jsonl"));
+            Command::Render { input, output }
+        }
+        Some("replay") => {
+            let path = args
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("Missing timeline file path\n{}", USAGE))?;
+            Command::Replay { path: path.into() }
+        }
+        Some("mock-server") => {
+            let addr = args.next().unwrap_or_else(|| "127.0.0.1:18233".to_string());
+            Command::MockServer { addr }
+        }
+        Some(other) => return Err(anyhow::anyhow!("Unknown command '{}'\n{}", other, USAGE)),
+    };
+    Ok(Args {
+        command,
+        server,
+        device,
+    })
+}
+
+/// The value of option `name` when `arg` is it, given as `name value` or
+/// `name=value`.
+fn _option_value(
+    name: &str,
+    arg: &str,
+    raw: &mut impl Iterator<Item = String>,
+) -> anyhow::Result<Option<String>> {
+    if arg == name {
+        let value = raw
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Missing value for {}\n{}", name, USAGE))?;
+        return Ok(Some(value));
+    }
+    Ok(arg
+        .strip_prefix(name)
+        .and_then(|rest| rest.strip_prefix('='))
+        .map(str::to_string))
+}
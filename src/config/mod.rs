@@ -0,0 +1,694 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+pub(crate) const CONFIG_PATH: &str = "config/config.json";
+/// How often the config file is checked for changes.
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Everything read from `config/config.json`. Every field has a default so
+/// an older or partial file keeps working; see the README for what they do.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Milliseconds of audio analyzed at once.
+    pub update_time: u64,
+    /// Samples per FFT frame, a power of two.
+    pub fft_size: usize,
+    /// Samples between the start of two FFT frames; frames overlap when it
+    /// is below `fft_size`.
+    pub fft_hop: usize,
+    pub fft_window: WindowFunction,
+    /// How long each vibration lasts, in milliseconds, unless the band's
+    /// envelope says otherwise.
+    pub vibration_time: u32,
+    pub debug: bool,
+    /// Route each band to the left or right half of the suit when one
+    /// channel is louder by more than `stereo_threshold`.
+    pub stereo: bool,
+    /// With `stereo`, fade each band across the suit's columns following
+    /// the left/right balance instead of switching sides.
+    pub panning: bool,
+    /// Analyze the average of all channels; when off every channel is
+    /// analyzed on its own and the band energies are added up.
+    pub downmix: bool,
+    /// Map each channel of 5.1/7.1 audio onto the region of the suit it
+    /// points at.
+    pub surround: bool,
+    /// Measure each band against its own recent peak instead of the fixed
+    /// `default_max_intensity`, so the volume doesn't matter.
+    pub auto_gain: bool,
+    /// Milliseconds for the auto gain peak to follow a louder sound.
+    pub auto_gain_attack: u32,
+    /// Milliseconds for the auto gain peak to come back down.
+    pub auto_gain_release: u32,
+    /// Lowest the auto gain peak can go, in percent of the band's max
+    /// intensity.
+    pub auto_gain_floor: i32,
+    /// Only vibrate, with a short pulse, when a band starts a new sound
+    /// instead of following its level.
+    pub onset: bool,
+    /// How far above its recent average a band's energy gain has to be to
+    /// count as an onset.
+    pub onset_sensitivity: f32,
+    /// Milliseconds of history the onset average is taken over.
+    pub onset_history: u32,
+    /// Length of an onset pulse in milliseconds; a band can't trigger again
+    /// before it is over.
+    pub onset_pulse_time: u32,
+    /// Strength of an onset pulse, in percent.
+    pub onset_intensity: i32,
+    /// Print the tempo guessed from the first band's onsets.
+    pub tempo: bool,
+    /// Left/right balance difference, in percent, above which only one side
+    /// of the suit vibrates.
+    pub stereo_threshold: i32,
+    /// The frequency bands to analyze. Filled from the legacy flat fields by
+    /// `load` when the file has no `bands`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bands: Option<Vec<BandConfig>>,
+    /// Name of the band the LFE channel is measured with in surround mode.
+    pub lfe_band: String,
+    /// Pattern driven by the LFE channel in surround mode.
+    pub pattern_lfe: String,
+    /// Audio device to capture by index, name or part of its name, see
+    /// `audio::init`. The default output device when missing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+    /// Same as `device` for the `input` mode, the default input device when
+    /// missing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_device: Option<String>,
+    /// WebSocket URL of the TrueGear app, see `true_gear::parse_server_url`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_url: Option<String>,
+    /// The `*_bass`/`*_other`/`*_treble` fields from before `bands`, only
+    /// read.
+    #[serde(flatten, skip_serializing)]
+    legacy: LegacyBands,
+}
+
+/// One frequency band and how the suit reacts to it.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub(crate) struct BandConfig {
+    pub name: String,
+    pub start_freq: u32,
+    pub end_freq: u32,
+    /// Energy that maps to 100%, the lower the more sensitive.
+    pub default_max_intensity: f32,
+    /// Same as `default_max_intensity` for a single channel, used by stereo
+    /// and surround.
+    pub stereo_default_max_intensity: f32,
+    /// Intensity, in percent, the band has to exceed to vibrate.
+    pub intensity_percent: i32,
+    /// Strength sent once the intensity goes above it.
+    pub intensity_max_percent: i32,
+    pub pattern: String,
+    pub pattern_left: String,
+    pub pattern_right: String,
+    pub envelope: Envelope,
+}
+
+impl Default for BandConfig {
+    fn default() -> BandConfig {
+        BandConfig {
+            name: String::new(),
+            start_freq: 20,
+            end_freq: 150,
+            default_max_intensity: 110.0,
+            stereo_default_max_intensity: 55.0,
+            intensity_percent: 30,
+            intensity_max_percent: 80,
+            pattern: "all".to_string(),
+            pattern_left: "left_all".to_string(),
+            pattern_right: "right_all".to_string(),
+            envelope: Envelope::default(),
+        }
+    }
+}
+
+/// Shape of each vibration of a band.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub(crate) struct Envelope {
+    /// Milliseconds, `vibration_time` when missing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>,
+    pub mode: EnvelopeMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum EnvelopeMode {
+    /// Same strength for the whole duration.
+    #[default]
+    Const,
+    /// Starts at the strength and fades to nothing.
+    FadeOut,
+    /// Starts from nothing and grows to the strength.
+    FadeIn,
+    /// Grows to the strength and fades back.
+    Pulse,
+}
+
+/// Window applied to each FFT frame.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum WindowFunction {
+    Rectangular,
+    #[default]
+    Hann,
+    Blackman,
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+struct LegacyBands {
+    start_freq_bass: u32,
+    end_freq_bass: u32,
+    start_freq_other: u32,
+    end_freq_other: u32,
+    start_freq_treble: u32,
+    end_freq_treble: u32,
+    bass_default_max_intensity: f32,
+    other_default_max_intensity: f32,
+    treble_default_max_intensity: f32,
+    bass_intensity_percent: i32,
+    bass_intensity_max_percent: i32,
+    other_intensity_percent: i32,
+    other_intensity_max_percent: i32,
+    treble_intensity_percent: i32,
+    treble_intensity_max_percent: i32,
+    stereo_bass_default_max_intensity: f32,
+    stereo_other_default_max_intensity: f32,
+    stereo_treble_default_max_intensity: f32,
+    pattern_bass: String,
+    pattern_other: String,
+    pattern_treble: String,
+    pattern_bass_left: String,
+    pattern_bass_right: String,
+    pattern_other_left: String,
+    pattern_other_right: String,
+    pattern_treble_left: String,
+    pattern_treble_right: String,
+}
+
+impl Default for LegacyBands {
+    fn default() -> LegacyBands {
+        LegacyBands {
+            start_freq_bass: 20,
+            end_freq_bass: 150,
+            start_freq_other: 150,
+            end_freq_other: 2000,
+            start_freq_treble: 2000,
+            end_freq_treble: 8000,
+            bass_default_max_intensity: 110.0,
+            other_default_max_intensity: 420.0,
+            treble_default_max_intensity: 550.0,
+            bass_intensity_percent: 30,
+            bass_intensity_max_percent: 80,
+            other_intensity_percent: 30,
+            other_intensity_max_percent: 60,
+            treble_intensity_percent: 30,
+            treble_intensity_max_percent: 40,
+            stereo_bass_default_max_intensity: 55.0,
+            stereo_other_default_max_intensity: 210.0,
+            stereo_treble_default_max_intensity: 275.0,
+            pattern_bass: "all".to_string(),
+            pattern_other: "middle_all".to_string(),
+            pattern_treble: "around_all".to_string(),
+            pattern_bass_left: "left_all".to_string(),
+            pattern_bass_right: "right_all".to_string(),
+            pattern_other_left: "left_all".to_string(),
+            pattern_other_right: "right_all".to_string(),
+            pattern_treble_left: "left_all".to_string(),
+            pattern_treble_right: "right_all".to_string(),
+        }
+    }
+}
+
+impl LegacyBands {
+    /// The three bands the flat fields describe.
+    fn to_bands(&self) -> Vec<BandConfig> {
+        let band =
+            |name: &str,
+             (start_freq, end_freq),
+             (default_max_intensity, stereo_default_max_intensity),
+             (intensity_percent, intensity_max_percent),
+             [pattern, pattern_left, pattern_right]: [&String; 3]| BandConfig {
+                name: name.to_string(),
+                start_freq,
+                end_freq,
+                default_max_intensity,
+                stereo_default_max_intensity,
+                intensity_percent,
+                intensity_max_percent,
+                pattern: pattern.clone(),
+                pattern_left: pattern_left.clone(),
+                pattern_right: pattern_right.clone(),
+                envelope: Envelope::default(),
+            };
+        vec![
+            band(
+                "bass",
+                (self.start_freq_bass, self.end_freq_bass),
+                (
+                    self.bass_default_max_intensity,
+                    self.stereo_bass_default_max_intensity,
+                ),
+                (self.bass_intensity_percent, self.bass_intensity_max_percent),
+                [
+                    &self.pattern_bass,
+                    &self.pattern_bass_left,
+                    &self.pattern_bass_right,
+                ],
+            ),
+            band(
+                "other",
+                (self.start_freq_other, self.end_freq_other),
+                (
+                    self.other_default_max_intensity,
+                    self.stereo_other_default_max_intensity,
+                ),
+                (
+                    self.other_intensity_percent,
+                    self.other_intensity_max_percent,
+                ),
+                [
+                    &self.pattern_other,
+                    &self.pattern_other_left,
+                    &self.pattern_other_right,
+                ],
+            ),
+            band(
+                "treble",
+                (self.start_freq_treble, self.end_freq_treble),
+                (
+                    self.treble_default_max_intensity,
+                    self.stereo_treble_default_max_intensity,
+                ),
+                (
+                    self.treble_intensity_percent,
+                    self.treble_intensity_max_percent,
+                ),
+                [
+                    &self.pattern_treble,
+                    &self.pattern_treble_left,
+                    &self.pattern_treble_right,
+                ],
+            ),
+        ]
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        let legacy = LegacyBands::default();
+        Config {
+            update_time: 50,
+            fft_size: 2048,
+            fft_hop: 1024,
+            fft_window: WindowFunction::Hann,
+            vibration_time: 60,
+            debug: false,
+            stereo: false,
+            panning: false,
+            downmix: true,
+            surround: false,
+            auto_gain: false,
+            auto_gain_attack: 100,
+            auto_gain_release: 5000,
+            auto_gain_floor: 10,
+            onset: false,
+            onset_sensitivity: 1.5,
+            onset_history: 1000,
+            onset_pulse_time: 80,
+            onset_intensity: 90,
+            tempo: false,
+            stereo_threshold: 30,
+            bands: Some(legacy.to_bands()),
+            lfe_band: "bass".to_string(),
+            pattern_lfe: "all".to_string(),
+            device: None,
+            input_device: None,
+            server_url: None,
+            legacy,
+        }
+    }
+}
+
+impl Config {
+    pub(crate) fn update_interval(&self) -> Duration {
+        Duration::from_millis(self.update_time)
+    }
+
+    pub(crate) fn bands(&self) -> &[BandConfig] {
+        self.bands.as_deref().unwrap_or_default()
+    }
+
+    /// Rejects values that would make the analysis misbehave instead of
+    /// failing loudly.
+    pub(crate) fn validate(&self) -> anyhow::Result<()> {
+        if !(10..=1000).contains(&self.update_time) {
+            return Err(anyhow::anyhow!(
+                "update_time must be between 10 and 1000 ms"
+            ));
+        }
+        if !(1..=10_000).contains(&self.vibration_time) {
+            return Err(anyhow::anyhow!(
+                "vibration_time must be between 1 and 10000 ms"
+            ));
+        }
+        if !self.fft_size.is_power_of_two() || !(256..=16_384).contains(&self.fft_size) {
+            return Err(anyhow::anyhow!(
+                "fft_size must be a power of two between 256 and 16384, got {}",
+                self.fft_size
+            ));
+        }
+        if !(1..=self.fft_size).contains(&self.fft_hop) {
+            return Err(anyhow::anyhow!(
+                "fft_hop must be between 1 and fft_size ({}), got {}",
+                self.fft_size,
+                self.fft_hop
+            ));
+        }
+        _check_percent("stereo_threshold", self.stereo_threshold)?;
+        _check_percent("auto_gain_floor", self.auto_gain_floor)?;
+        _check_percent("onset_intensity", self.onset_intensity)?;
+        if self.onset_sensitivity <= 0.0 {
+            return Err(anyhow::anyhow!("onset_sensitivity must be above 0"));
+        }
+        for (field, value) in [
+            ("auto_gain_attack", self.auto_gain_attack),
+            ("auto_gain_release", self.auto_gain_release),
+            ("onset_history", self.onset_history),
+            ("onset_pulse_time", self.onset_pulse_time),
+        ] {
+            if !(1..=60_000).contains(&value) {
+                return Err(anyhow::anyhow!("{} must be between 1 and 60000 ms", field));
+            }
+        }
+        _check_pattern("pattern_lfe", &self.pattern_lfe)?;
+        // Only surround reads it, other configs may not have a band by that name.
+        if self.surround && !self.bands().iter().any(|b| b.name == self.lfe_band) {
+            return Err(anyhow::anyhow!(
+                "lfe_band: there is no band named '{}'",
+                self.lfe_band
+            ));
+        }
+
+        for (i, band) in self.bands().iter().enumerate() {
+            let name = &band.name;
+            if name.is_empty() {
+                return Err(anyhow::anyhow!("bands[{}] has no name", i));
+            }
+            if self.bands()[..i].iter().any(|b| &b.name == name) {
+                return Err(anyhow::anyhow!(
+                    "there is more than one band named '{}'",
+                    name
+                ));
+            }
+            if band.start_freq >= band.end_freq {
+                return Err(anyhow::anyhow!(
+                    "band '{}': start_freq ({}) must be lower than end_freq ({})",
+                    name,
+                    band.start_freq,
+                    band.end_freq
+                ));
+            }
+            // Audio is analyzed at ANALYSIS_RATE, nothing above its Nyquist
+            // frequency is left.
+            if band.end_freq > crate::analysis::ANALYSIS_RATE / 2 {
+                return Err(anyhow::anyhow!(
+                    "band '{}': end_freq ({}) must be at most {} Hz",
+                    name,
+                    band.end_freq,
+                    crate::analysis::ANALYSIS_RATE / 2
+                ));
+            }
+            if band.default_max_intensity <= 0.0 {
+                return Err(anyhow::anyhow!(
+                    "band '{}': default_max_intensity must be above 0",
+                    name
+                ));
+            }
+            if band.stereo_default_max_intensity <= 0.0 {
+                return Err(anyhow::anyhow!(
+                    "band '{}': stereo_default_max_intensity must be above 0",
+                    name
+                ));
+            }
+            _check_percent(
+                &format!("band '{}': intensity_percent", name),
+                band.intensity_percent,
+            )?;
+            _check_percent(
+                &format!("band '{}': intensity_max_percent", name),
+                band.intensity_max_percent,
+            )?;
+            if band
+                .envelope
+                .duration
+                .is_some_and(|d| !(1..=10_000).contains(&d))
+            {
+                return Err(anyhow::anyhow!(
+                    "band '{}': envelope duration must be between 1 and 10000 ms",
+                    name
+                ));
+            }
+            _check_pattern(&format!("band '{}': pattern", name), &band.pattern)?;
+            _check_pattern(
+                &format!("band '{}': pattern_left", name),
+                &band.pattern_left,
+            )?;
+            _check_pattern(
+                &format!("band '{}': pattern_right", name),
+                &band.pattern_right,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn _check_pattern(field: &str, pattern: &str) -> anyhow::Result<()> {
+    if crate::analysis::pattern_indexes(pattern).is_none() {
+        return Err(anyhow::anyhow!("{}: unknown pattern '{}'", field, pattern));
+    }
+    Ok(())
+}
+
+fn _check_percent(field: &str, value: i32) -> anyhow::Result<()> {
+    if !(0..=100).contains(&value) {
+        return Err(anyhow::anyhow!(
+            "{} must be between 0 and 100, got {}",
+            field,
+            value
+        ));
+    }
+    Ok(())
+}
+
+/// Reads and validates the config, writing the defaults to `CONFIG_PATH`
+/// first when there is no config yet.
+pub(crate) fn load() -> anyhow::Result<Config> {
+    if !Path::new(CONFIG_PATH).exists() {
+        _write_default()?;
+    }
+    let config_str = fs::read_to_string(CONFIG_PATH)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", CONFIG_PATH, e))?;
+    let config = _parse(&config_str)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", CONFIG_PATH, e))?;
+    config
+        .validate()
+        .map_err(|e| anyhow::anyhow!("Invalid {}: {}", CONFIG_PATH, e))?;
+    Ok(config)
+}
+
+/// Deserializes a config, building `bands` from the legacy flat fields when
+/// it has none. Not validated.
+fn _parse(json: &str) -> serde_json::Result<Config> {
+    let mut config: Config = serde_json::from_str(json)?;
+    if config.bands.is_none() {
+        config.bands = Some(config.legacy.to_bands());
+    }
+    Ok(config)
+}
+
+fn _write_default() -> anyhow::Result<()> {
+    let path = Path::new(CONFIG_PATH);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string_pretty(&Config::default())?;
+    fs::write(path, json).map_err(|e| anyhow::anyhow!("Failed to write {}: {}", CONFIG_PATH, e))?;
+    println!("No config found, wrote the default one to {}", CONFIG_PATH);
+    Ok(())
+}
+
+/// The current config, shared between the analysis and the file watcher.
+#[derive(Clone)]
+pub(crate) struct ConfigHandle {
+    current: Arc<RwLock<Arc<Config>>>,
+}
+
+impl ConfigHandle {
+    /// Snapshot of the config; it stays consistent for as long as it is held
+    /// even if the file is reloaded meanwhile.
+    pub(crate) fn get(&self) -> Arc<Config> {
+        self.current.read().unwrap().clone()
+    }
+}
+
+/// Loads the config and keeps it up to date by polling the file's mtime.
+/// An edit that fails to parse or validate is reported and the previous
+/// config stays in use.
+pub(crate) fn watch() -> anyhow::Result<ConfigHandle> {
+    let handle = ConfigHandle {
+        current: Arc::new(RwLock::new(Arc::new(load()?))),
+    };
+    let current = handle.current.clone();
+    tokio::spawn(async move {
+        let mut modified = _modified();
+        let mut ticker = tokio::time::interval(CONFIG_POLL_INTERVAL);
+        loop {
+            ticker.tick().await;
+            let now = _modified();
+            if now == modified || now.is_none() {
+                continue;
+            }
+            modified = now;
+            match load() {
+                Ok(config) => {
+                    *current.write().unwrap() = Arc::new(config);
+                    println!("Reloaded {}", CONFIG_PATH);
+                }
+                Err(e) => eprintln!("⚠️ {}, keeping the previous config", e),
+            }
+        }
+    });
+    Ok(handle)
+}
+
+fn _modified() -> Option<SystemTime> {
+    fs::metadata(CONFIG_PATH).and_then(|m| m.modified()).ok()
+}
+
+/// Picks and validates the TrueGear server URL: the command line wins over
+/// `server_url` from the config, which wins over the default local app.
+pub(crate) fn server_url(cli: Option<&str>) -> anyhow::Result<String> {
+    let url = match cli {
+        Some(url) => url.to_string(),
+        None if Path::new(CONFIG_PATH).exists() => load()?
+            .server_url
+            .unwrap_or_else(|| crate::true_gear::TRUE_GEAR_SERVER.to_string()),
+        None => crate::true_gear::TRUE_GEAR_SERVER.to_string(),
+    };
+    crate::true_gear::parse_server_url(&url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The error `validate` gives for the default config with `json` merged
+    /// into its first band.
+    fn _band_error(json: serde_json::Value) -> String {
+        let mut config = Config::default();
+        let mut band = serde_json::to_value(&config.bands()[0]).unwrap();
+        for (key, value) in json.as_object().unwrap() {
+            band[key] = value.clone();
+        }
+        config.bands.as_mut().unwrap()[0] = serde_json::from_value(band).unwrap();
+        config.validate().unwrap_err().to_string()
+    }
+
+    #[test]
+    fn legacy_config_loads_into_three_bands() {
+        // A config.json from before bands existed.
+        let config = _parse(
+            r#"{"start_freq_bass":20,"end_freq_bass":150,"start_freq_other":150,
+            "end_freq_other":2000,"start_freq_treble":2000,"end_freq_treble":8000,
+            "bass_default_max_intensity":30,"other_default_max_intensity":40,
+            "treble_default_max_intensity":50,"bass_intensity_percent":20,
+            "bass_intensity_max_percent":80,"other_intensity_percent":25,
+            "other_intensity_max_percent":70,"treble_intensity_percent":30,
+            "treble_intensity_max_percent":60,"pattern_bass":"all",
+            "pattern_other":"front","pattern_treble":"back"}"#,
+        )
+        .unwrap();
+        config.validate().unwrap();
+        let bands: Vec<_> = config
+            .bands()
+            .iter()
+            .map(|b| {
+                (
+                    b.name.as_str(),
+                    b.start_freq,
+                    b.end_freq,
+                    b.default_max_intensity,
+                    b.intensity_percent,
+                    b.intensity_max_percent,
+                    b.pattern.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            bands,
+            [
+                ("bass", 20, 150, 30.0, 20, 80, "all"),
+                ("other", 150, 2000, 40.0, 25, 70, "front"),
+                ("treble", 2000, 8000, 50.0, 30, 60, "back"),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_config_is_the_valid_default() {
+        let config = _parse("{}").unwrap();
+        config.validate().unwrap();
+        assert_eq!(
+            serde_json::to_value(&config).unwrap(),
+            serde_json::to_value(Config::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_a_band_ending_before_it_starts() {
+        let error = _band_error(serde_json::json!({"start_freq": 150, "end_freq": 150}));
+        assert!(error.contains("start_freq"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_a_percent_out_of_range() {
+        let error = _band_error(serde_json::json!({"intensity_percent": 101}));
+        assert!(error.contains("intensity_percent"), "{}", error);
+        let error = _band_error(serde_json::json!({"intensity_max_percent": -1}));
+        assert!(error.contains("intensity_max_percent"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_an_unknown_pattern() {
+        let error = _band_error(serde_json::json!({"pattern": "everywhere"}));
+        assert!(error.contains("unknown pattern 'everywhere'"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_a_duplicate_band_name() {
+        let error = _band_error(serde_json::json!({"name": "other"}));
+        assert!(
+            error.contains("more than one band named 'other'"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn rejects_a_zero_hop() {
+        let config = _parse(r#"{"fft_hop": 0}"#).unwrap();
+        let error = config.validate().unwrap_err().to_string();
+        assert!(error.contains("fft_hop"), "{}", error);
+    }
+}
@@ -1,401 +1,287 @@
-use crate::true_gear::def::TrackObject;
+use crate::cli::Command;
+use crate::config::Config;
+use crate::true_gear::def::ServerResponse;
 use crate::true_gear::TrueGearClient;
-use cpal::traits::{DeviceTrait, StreamTrait};
-use cpal::{FromSample, Sample};
-use hound::{SampleFormat, WavSpec};
-use once_cell::sync::Lazy;
-use rustfft::num_complex::Complex;
-use rustfft::FftPlanner;
-use std::collections::HashMap;
-use std::io::Cursor;
-use std::sync::{Arc, Mutex};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::select;
-use serde::Deserialize;
-use std::fs;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Mutex;
 
+mod analysis;
 mod audio;
+mod cli;
+mod config;
+mod timeline;
 mod true_gear;
+mod wav;
 
-static DATA_BUFFER: Lazy<Arc<Mutex<Vec<u8>>>> = Lazy::new(|| Arc::new(Mutex::new(Vec::new())));
+/// How long startup waits for the TrueGear app to acknowledge the connection.
+const READY_TIMEOUT: Duration = Duration::from_secs(10);
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+fn main() -> anyhow::Result<()> {
     println!("TrueGear Audio Driver v0.0.1 by xkeyC");
 
-    
-    let audio_device = audio::init()?;
-    let audio_config = audio_device.default_output_config()?;
+    let args = cli::parse()?;
+    // Changes the environment, so before the runtime starts any thread.
+    #[cfg(target_os = "linux")]
+    if matches!(args.command, Command::Capture) {
+        audio::init_monitor_source();
+    }
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(run(args))
+}
 
-    println!("Connecting to TrueGear ...");
-    let mut client = true_gear::connect().await?;
-    client.test_all().await?; 
+async fn run(args: cli::Args) -> anyhow::Result<()> {
+    let server = args.server.as_deref();
+    match args.command {
+        Command::Capture => {
+            let server_url = config::server_url(server)?;
+            run_capture(&server_url, args.device.as_deref(), audio::Source::Output).await
+        }
+        Command::Input => {
+            let server_url = config::server_url(server)?;
+            run_capture(&server_url, args.device.as_deref(), audio::Source::Input).await
+        }
+        Command::Devices => run_devices(),
+        Command::Play { path } => run_play(&path, &config::server_url(server)?).await,
+        Command::Render { input, output } => run_render(&input, &output),
+        Command::Replay { path } => run_replay(&path, &config::server_url(server)?).await,
+        Command::MockServer { addr } => run_mock_server(&addr).await,
+    }
+}
 
-    let client_arc = Arc::new(Mutex::new(client));
-    let client_arc_clone = client_arc.clone();
+async fn connect_client(server_url: &str) -> anyhow::Result<TrueGearClient> {
+    println!("Connecting to TrueGear at {} ...", server_url);
+    let client = true_gear::connect(server_url).await?;
+    let mut responses = client.subscribe();
+    tokio::spawn(async move {
+        // Answers of an unknown shape are shown once, so the next ones can be
+        // decoded too; acks are the norm and errors are already logged.
+        let mut last_other = None;
+        loop {
+            match responses.recv().await {
+                Ok(ServerResponse::Other { method, result }) => {
+                    if last_other.as_ref() != Some(&(method.clone(), result.clone())) {
+                        println!("[TrueGear] {} == {}", method, result);
+                        last_other = Some((method, result));
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+    if let Err(e) = client.wait_ready(READY_TIMEOUT).await {
+        eprintln!("⚠️ {}, continuing anyway", e);
+    }
+    Ok(client)
+}
 
-    let err_fn = move |err| {
-        eprintln!("an error occurred on stream: {}", err);
+async fn run_capture(
+    server_url: &str,
+    device: Option<&str>,
+    source: audio::Source,
+) -> anyhow::Result<()> {
+    let config_handle = config::watch()?;
+    let config = config_handle.get();
+    let configured = match source {
+        audio::Source::Output => config.device.as_deref(),
+        audio::Source::Input => config.input_device.as_deref(),
     };
-    let spec = wav_spec_from_config(&audio_config);
-    println!("Audio spec == {:?}", spec);
+    let selector = device.or(configured).map(String::from);
+    let mut capture = audio::capture::Capture::start(selector, source)?;
 
-    let stream = match audio_config.sample_format() {
-        cpal::SampleFormat::I8 => audio_device.build_input_stream(
-            &audio_config.into(),
-            move |data, _: &_| write_input_data::<i8, i8>(data, spec),
-            err_fn,
-            None,
-        )?,
-        cpal::SampleFormat::I16 => audio_device.build_input_stream(
-            &audio_config.into(),
-            move |data, _: &_| write_input_data::<i16, i16>(data, spec),
-            err_fn,
-            None,
-        )?,
-        cpal::SampleFormat::I32 => audio_device.build_input_stream(
-            &audio_config.into(),
-            move |data, _: &_| write_input_data::<i32, i32>(data, spec),
-            err_fn,
-            None,
-        )?,
-        cpal::SampleFormat::F32 => audio_device.build_input_stream(
-            &audio_config.into(),
-            move |data, _: &_| write_input_data::<f32, f32>(data, spec),
-            err_fn,
-            None,
-        )?,
-        sample_format => {
-            return Err(anyhow::Error::msg(format!(
-                "Unsupported sample format '{sample_format}'"
-            )))
-        }
-    };
+    let mut client = connect_client(server_url).await?;
+    client.test_all().await?;
 
-    stream.play()?;
+    let client_arc = Arc::new(Mutex::new(client));
+    let client_arc_clone = client_arc.clone();
 
     tokio::spawn(async move {
+        let order = analysis::ChannelOrder::native();
+        let mut analyzer = analysis::Analyzer::with_channel_order(order);
         loop {
-            tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
-            let mut buffer = DATA_BUFFER.lock().expect("Failed to lock buffer");
-            if !buffer.is_empty() {
-                do_audio_fft(buffer.clone(), client_arc_clone.clone());
-                buffer.clear();
+            tokio::time::sleep(config_handle.get().update_interval()).await;
+            if let Some((samples, channels, sample_rate)) = capture.read() {
+                do_audio_fft(
+                    &samples,
+                    channels,
+                    sample_rate,
+                    &mut analyzer,
+                    &config_handle.get(),
+                    client_arc_clone.clone(),
+                );
             }
         }
     });
 
     select! {
         _ = tokio::signal::ctrl_c() => {
-            drop(stream);
-            client_arc.clone().try_lock().unwrap().close().await?;
+            client_arc.lock().await.close().await?;
         }
     }
     Ok(())
 }
 
-#[derive(Deserialize)]
-struct Config {
-    start_freq_bass: i16,
-    end_freq_bass: i16,
-    start_freq_other: i16,
-    end_freq_other: i16,
-    start_freq_treble: i16,
-    end_freq_treble: i16,
-    bass_default_max_intensity: f32,
-    other_default_max_intensity: f32,
-    treble_default_max_intensity: f32,
-    bass_intensity_percent: i32,
-    bass_intensity_max_percent: i16,
-    other_intensity_percent: i32,
-    other_intensity_max_percent: i16,
-    treble_intensity_percent: i32,
-    treble_intensity_max_percent: i16,
-    pattern_bass: String,
-    pattern_other: String,
-    pattern_treble: String,
-
-}
-
-fn load_config() -> Config {
-    let config_str = fs::read_to_string("config/config.json")
-        .expect("Failed to read config.json");
-    serde_json::from_str(&config_str).expect("Failed to parse config.json")
-}
-
-
-fn pattern_to_vec(name: &str) -> Vec<i32> {
-    match name {
-        "middle_front" => crate::true_gear::TRUE_GEAR_SHAKE_MIDDLE_FRONT.to_vec(),
-        "middle_back" => crate::true_gear::TRUE_GEAR_SHAKE_MIDDLE_BACK.to_vec(),
-        "around_front" => crate::true_gear::TRUE_GEAR_SHAKE_AROUND_FRONT.to_vec(),
-        "around_back" => crate::true_gear::TRUE_GEAR_SHAKE_AROUND_BACK.to_vec(),
-        "up_front" => crate::true_gear::TRUE_GEAR_SHAKE_UP_FRONT.to_vec(),
-        "up_back" => crate::true_gear::TRUE_GEAR_SHAKE_UP_BACK.to_vec(),
-        "down_front" => crate::true_gear::TRUE_GEAR_SHAKE_DOWN_FRONT.to_vec(),
-        "down_back" => crate::true_gear::TRUE_GEAR_SHAKE_DOWN_BACK.to_vec(),
-        "front" => crate::true_gear::TRUE_GEAR_SHAKE_FRONT.to_vec(),
-        "back" => crate::true_gear::TRUE_GEAR_SHAKE_BACK.to_vec(),
-        "middle_all" => {
-            let mut v = Vec::new();
-            v.extend(crate::true_gear::TRUE_GEAR_SHAKE_MIDDLE_FRONT.iter().cloned());
-            v.extend(crate::true_gear::TRUE_GEAR_SHAKE_MIDDLE_BACK.iter().cloned());
-            v
-        }
-        "around_all" => {
-            let mut v = Vec::new();
-            v.extend(crate::true_gear::TRUE_GEAR_SHAKE_AROUND_FRONT.iter().cloned());
-            v.extend(crate::true_gear::TRUE_GEAR_SHAKE_AROUND_BACK.iter().cloned());
-            v
-        }
-        "up_all" => {
-            let mut v = Vec::new();
-            v.extend(crate::true_gear::TRUE_GEAR_SHAKE_UP_FRONT.iter().cloned());
-            v.extend(crate::true_gear::TRUE_GEAR_SHAKE_UP_BACK.iter().cloned());
-            v
-        }
-        "down_all" => {
-            let mut v = Vec::new();
-            v.extend(crate::true_gear::TRUE_GEAR_SHAKE_DOWN_FRONT.iter().cloned());
-            v.extend(crate::true_gear::TRUE_GEAR_SHAKE_DOWN_BACK.iter().cloned());
-            v
-        }
-        "all" => {
-            let mut v = Vec::new();
-            v.extend(crate::true_gear::TRUE_GEAR_SHAKE_MIDDLE_FRONT.iter().cloned());
-            v.extend(crate::true_gear::TRUE_GEAR_SHAKE_MIDDLE_BACK.iter().cloned());
-            v.extend(crate::true_gear::TRUE_GEAR_SHAKE_AROUND_FRONT.iter().cloned());
-            v.extend(crate::true_gear::TRUE_GEAR_SHAKE_AROUND_BACK.iter().cloned());
-            v
-        }
-        _ => {
-            eprintln!("⚠️ Unknown pattern: {}", name);
-            Vec::new()
-        }
+/// Prints every audio device `--device` can pick.
+fn run_devices() -> anyhow::Result<()> {
+    let devices = audio::list()?;
+    if devices.is_empty() {
+        println!("No audio device found");
     }
-}
-
-fn do_audio_fft(wav_data: Vec<u8>, client_arc_clone: Arc<Mutex<TrueGearClient>>) {
-
-    let config = load_config();
-
-    let mut true_gear_msg_vec: Vec<TrackObject> = Vec::new();
-    let spectrum = _get_fft(wav_data);
-    let freq_vec: Vec<_> = spectrum.keys().copied().collect();
-    if freq_vec.is_empty() {
-        return; // pas de data audio
+    for device in devices {
+        println!("{}", device);
     }
+    Ok(())
+}
 
-    let max_freq = freq_vec.iter().max().unwrap();
-    
-    let freq_interval = *max_freq as f32 / 5.0;
-    let start_freq_bass = config.start_freq_bass;
-    let end_freq_bass = config.end_freq_bass;
-    let start_freq_other = config.start_freq_other;
-    let end_freq_other = config.end_freq_other;
-    let start_freq_treble = config.start_freq_treble;
-    let end_freq_treble = config.end_freq_treble;
-//    let end_freq = freq_interval as i16;
-
-    
-    let mut bass_intensity = 0.0;
-    for (freq, &value) in spectrum.iter() {
-        if *freq >= start_freq_bass && *freq < end_freq_bass {
-            bass_intensity += value;
+/// Feeds a WAV file through the same analysis as the live capture, one
+/// `update_time` block at a time and paced in real time.
+async fn run_play(path: &Path, server_url: &str) -> anyhow::Result<()> {
+    let config_handle = config::watch()?;
+    let update_interval = config_handle.get().update_interval();
+    let chunks = wav::WavChunks::open(path, update_interval)?;
+    let spec = chunks.spec();
+    println!(
+        "Playing {} ({:.1}s) == {:?}",
+        path.display(),
+        chunks.duration().as_secs_f32(),
+        spec
+    );
+
+    let mut client = connect_client(server_url).await?;
+
+    let mut analyzer = analysis::Analyzer::new();
+    let mut ticker = tokio::time::interval(update_interval);
+    for chunk in chunks {
+        let (_, samples) = chunk?;
+        select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => break,
         }
-    }
-    let mut other_intensity = 0.0;
-    for (freq, &value) in spectrum.iter() {
-        if *freq >= start_freq_other && *freq < end_freq_other {
-            other_intensity += value;
-        }
-    }
-    let mut treble_intensity = 0.0;
-    for (freq, &value) in spectrum.iter() {
-        if *freq >= start_freq_treble && *freq < end_freq_treble {
-            treble_intensity += value;
+        let tracks = analyzer.analyze(&samples, spec.channels, spec.sample_rate, &config_handle.get());
+        if !tracks.is_empty() {
+            client.send_shake(tracks).await?;
         }
     }
 
-    
-    let bass_default_max_intensity = config.bass_default_max_intensity; // valeur haute pour les basses
-    bass_intensity = bass_intensity / 5.0;
-    let bass_intensity_percent = (bass_intensity / bass_default_max_intensity * 100.0) as i32;
-//    println!("DEBUG Config Intensity: {:?}", config.bass_default_max_intensity);
-    println!("DEBUG Bass Intensity: {:?}", bass_intensity_percent);
-    let other_default_max_intensity = config.other_default_max_intensity; // valeur haute pour les autres fréquences
-    other_intensity = other_intensity / 5.0;
-    let other_intensity_percent = (other_intensity / other_default_max_intensity * 100.0) as i32;
-    println!("DEBUG Other Intensity: {:?}", other_intensity_percent);
-    let treble_default_max_intensity = config.treble_default_max_intensity; // valeur haute pour les autres fréquences
-    treble_intensity = treble_intensity / 5.0;
-    let treble_intensity_percent = (treble_intensity / treble_default_max_intensity * 100.0) as i32;
-    println!("DEBUG Treble Intensity: {:?}", treble_intensity_percent);
-
-    if bass_intensity_percent > config.bass_intensity_percent.into() {
-        
-        let pattern_middle_front = crate::true_gear::TRUE_GEAR_SHAKE_MIDDLE_FRONT.to_vec();
-        let pattern_bass = config.pattern_bass;
-        let all_vec = pattern_to_vec(&pattern_bass);
-
-        if bass_intensity > config.bass_intensity_max_percent.into() {
-            let track = TrackObject::new_shake_duration(
-                Some(60),               
-                Some(config.bass_intensity_max_percent.into()), 
-                Some(config.bass_intensity_max_percent.into()), 
-                None,
-                all_vec,                 
-            );
-            true_gear_msg_vec.push(track);
+    println!("Playback finished");
+    client.close().await?;
+    Ok(())
+}
 
+/// Runs the analysis over a whole WAV file without pacing or a suit, writing
+/// every non-empty set of tracks with its offset into `output`.
+fn run_render(input: &Path, output: &Path) -> anyhow::Result<()> {
+    let config = config::load()?;
+    let chunks = wav::WavChunks::open(input, config.update_interval())?;
+    let spec = chunks.spec();
+    println!(
+        "Rendering {} ({:.1}s) == {:?}",
+        input.display(),
+        chunks.duration().as_secs_f32(),
+        spec
+    );
+
+    let file = File::create(output)
+        .map_err(|e| anyhow::anyhow!("Failed to create {}: {}", output.display(), e))?;
+    let mut writer = timeline::TimelineWriter::new(BufWriter::new(file));
+    let mut analyzer = analysis::Analyzer::new();
+    let mut entries = 0;
+    for chunk in chunks {
+        let (offset, samples) = chunk?;
+        let tracks = analyzer.analyze(&samples, spec.channels, spec.sample_rate, &config);
+        if !tracks.is_empty() {
+            writer.write(&timeline::TimelineEntry {
+                time_ms: offset.as_millis() as u64,
+                payload: timeline::TimelinePayload::Tracks(tracks),
+            })?;
+            entries += 1;
         }
-        else {
-            let track = TrackObject::new_shake_duration(
-                Some(60),               
-                Some(bass_intensity_percent-20), 
-                Some(bass_intensity_percent-20), 
-                None,
-                all_vec,                 
-            );
-            true_gear_msg_vec.push(track);
-        }
-
     }
-    if other_intensity_percent > config.other_intensity_percent {
-        
-        let pattern_other = config.pattern_other;
-        let all_vec = pattern_to_vec(&pattern_other);
-        if other_intensity > config.other_intensity_max_percent.into() {
-            let track = TrackObject::new_shake_duration(
-                Some(60),               
-                Some(config.other_intensity_max_percent.into()), 
-                Some(config.other_intensity_max_percent.into()), 
-                None,
-                all_vec,                 
-            );
-            true_gear_msg_vec.push(track);
+    writer.finish()?;
 
-        }
-        else {
-            let track = TrackObject::new_shake_duration(
-                Some(60),               
-                Some(bass_intensity_percent-20), 
-                Some(bass_intensity_percent-20), 
-                None,
-                all_vec,                 
-            );
-            true_gear_msg_vec.push(track);
-        }
-    }
-    if treble_intensity_percent > config.treble_intensity_percent {
-        
-        let pattern_treble = config.pattern_treble;
-        let all_vec = pattern_to_vec(&pattern_treble);
-        if treble_intensity > config.treble_intensity_max_percent.into() {
-            let track = TrackObject::new_shake_duration(
-                Some(60),               
-                Some(config.treble_intensity_max_percent.into()), 
-                Some(config.treble_intensity_max_percent.into()), 
-                None,
-                all_vec,                 
-            );
-            true_gear_msg_vec.push(track);
+    println!("Wrote {} entries to {}", entries, output.display());
+    Ok(())
+}
 
+/// Sends every entry of a timeline file to the suit at its offset from the
+/// moment playback started, without running any analysis.
+async fn run_replay(path: &Path, server_url: &str) -> anyhow::Result<()> {
+    let entries = timeline::read(path)?;
+    let length = entries.last().map(|e| e.time_ms).unwrap_or(0);
+    println!(
+        "Replaying {} ({} entries, {:.1}s)",
+        path.display(),
+        entries.len(),
+        length as f32 / 1000.0
+    );
+
+    let mut client = connect_client(server_url).await?;
+
+    let start = tokio::time::Instant::now();
+    for entry in entries {
+        select! {
+            _ = tokio::time::sleep_until(start + Duration::from_millis(entry.time_ms)) => {}
+            _ = tokio::signal::ctrl_c() => break,
         }
-        else {
-            let track = TrackObject::new_shake_duration(
-                Some(60),               
-                Some(treble_intensity_percent-20), 
-                Some(treble_intensity_percent-20), 
-                None,
-                all_vec,                 
-            );
-            true_gear_msg_vec.push(track);
+        match entry.payload {
+            timeline::TimelinePayload::Tracks(tracks) => client.send_shake(tracks).await?,
+            timeline::TimelinePayload::Message(message) => client.send_message(message).await?,
         }
     }
 
-    
-    tokio::spawn(async move {
-        let client_arc_clone = client_arc_clone.clone();
-        let true_gear_msg_vec = true_gear_msg_vec.clone();
-        tokio::task::spawn_blocking(move || {
-            let mut client = client_arc_clone.lock().unwrap();
-            tokio::runtime::Handle::current().block_on(async {
-                if !true_gear_msg_vec.is_empty() {
-                    client.send_shake(true_gear_msg_vec).await.unwrap();
-                }
-            });
-        })
-        .await
-        .unwrap();
-    });
+    println!("Replay finished");
+    client.close().await?;
+    Ok(())
 }
 
-fn _get_fft(data: Vec<u8>) -> HashMap<i16, f32> {
-    let mut reader = hound::WavReader::new(Cursor::new(data)).expect("Failed to read wav");
-    let spec = reader.spec();
-    let samples: Vec<f32> = match spec.sample_format {
-        SampleFormat::Int => match spec.bits_per_sample {
-            8 => reader
-                .samples::<i8>()
-                .map(|s| s.unwrap() as f32 / i8::MAX as f32)
-                .collect(),
-            16 => reader
-                .samples::<i16>()
-                .map(|s| s.unwrap() as f32 / i16::MAX as f32)
-                .collect(),
-            32 => reader
-                .samples::<i32>()
-                .map(|s| s.unwrap() as f32 / i32::MAX as f32)
-                .collect(),
-            _ => panic!("Unsupported bit depth"),
-        },
-        SampleFormat::Float => reader.samples::<f32>().map(|s| s.unwrap()).collect(),
-    };
+/// Runs the mock TrueGear server until Ctrl+C, logging what it receives.
+async fn run_mock_server(addr: &str) -> anyhow::Result<()> {
+    let mut server = true_gear::mock::MockServer::bind(addr).await?;
+    println!("Mock TrueGear server listening on {}", server.url());
 
-    let mut planner = FftPlanner::new();
-    let fft = planner.plan_fft_forward(samples.len());
-    let mut input: Vec<Complex<f32>> = samples
-        .iter()
-        .map(|&x| Complex { re: x, im: 0.0 })
-        .collect();
-    fft.process(&mut input);
-    let spectrum: Vec<_> = input.iter().map(|c| c.norm()).collect();
-    let mut result = HashMap::new();
-    for (i, &value) in spectrum.iter().enumerate() {
-        let freq = (i as f32 * spec.sample_rate as f32 / samples.len() as f32) as i16;
-        result.insert(freq, value);
+    loop {
+        select! {
+            message = server.recv() => {
+                let Some(message) = message else { break };
+                println!("[MockServer] {} == {} tracks", message.name, message.tracks.len());
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
     }
-    result
+
+    println!(
+        "Received {} messages, {} tracks",
+        server.received_messages(),
+        server.received_tracks().len()
+    );
+    Ok(())
 }
 
-fn wav_spec_from_config(config: &cpal::SupportedStreamConfig) -> WavSpec {
-    WavSpec {
-        channels: config.channels() as _,
-        sample_rate: config.sample_rate().0 as _,
-        bits_per_sample: (config.sample_format().sample_size() * 8) as _,
-        sample_format: SampleFormat::Int,
+fn do_audio_fft(
+    samples: &[f32],
+    channels: u16,
+    sample_rate: u32,
+    analyzer: &mut analysis::Analyzer,
+    config: &Config,
+    client_arc_clone: Arc<Mutex<TrueGearClient>>,
+) {
+    let true_gear_msg_vec = analyzer.analyze(samples, channels, sample_rate, config);
+    if true_gear_msg_vec.is_empty() {
+        return;
     }
-}
 
-fn write_input_data<T, U>(input: &[T], spec: WavSpec)
-where
-    T: Sample,
-    U: Sample + hound::Sample + FromSample<T>,
-{
-    if !input.is_empty() {
-        let mut buff = Cursor::new(Vec::new());
-        let mut writer = hound::WavWriter::new(&mut buff, spec);
-        for &sample in input.iter() {
-            writer.write_sample(U::from_sample(sample)).unwrap();
-        }
-        writer.finalize().unwrap();
-        let data = buff.into_inner();
-        let mut buffer = DATA_BUFFER.lock().unwrap();
-        // insert data to buffer, if DATA_BUFFER not empty, split header
-        if !buffer.is_empty() {
-            buffer.extend_from_slice(&data[44..]);
-        } else {
-            buffer.extend_from_slice(&data);
+    tokio::spawn(async move {
+        let mut client = client_arc_clone.lock().await;
+        if let Err(e) = client.send_shake(true_gear_msg_vec).await {
+            eprintln!("⚠️ Failed to send shake: {}", e);
         }
-    }
+    });
 }
@@ -0,0 +1,72 @@
+use crate::true_gear::def::{TrackObject, TrueGearWsMessage};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// One entry of a haptic timeline: what to play `time_ms` milliseconds after
+/// the start of the timeline.
+#[derive(Serialize, Deserialize, Debug)]
+pub(crate) struct TimelineEntry {
+    pub time_ms: u64,
+    #[serde(flatten)]
+    pub payload: TimelinePayload,
+}
+
+/// Either bare tracks, as written by `render`, or a complete message when the
+/// timeline was authored by hand with its own name/priority.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TimelinePayload {
+    Tracks(Vec<TrackObject>),
+    Message(TrueGearWsMessage),
+}
+
+/// Writes timeline entries as JSON lines.
+pub(crate) struct TimelineWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> TimelineWriter<W> {
+    pub(crate) fn new(writer: W) -> TimelineWriter<W> {
+        TimelineWriter { writer }
+    }
+
+    pub(crate) fn write(&mut self, entry: &TimelineEntry) -> anyhow::Result<()> {
+        serde_json::to_writer(&mut self.writer, entry)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    pub(crate) fn finish(mut self) -> anyhow::Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Reads a timeline from either JSON lines or a single JSON array, sorted by
+/// time.
+pub(crate) fn read(path: &Path) -> anyhow::Result<Vec<TimelineEntry>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+
+    let mut entries: Vec<TimelineEntry> = if content.trim_start().starts_with('[') {
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?
+    } else {
+        let mut entries = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(line).map_err(|e| {
+                anyhow::anyhow!("Failed to parse {} line {}: {}", path.display(), i + 1, e)
+            })?;
+            entries.push(entry);
+        }
+        entries
+    };
+
+    entries.sort_by_key(|e| e.time_ms);
+    Ok(entries)
+}
@@ -41,6 +41,7 @@ pub struct TrackObject {
 }
 
 impl TrackObject {
+    #[allow(dead_code, clippy::too_many_arguments)]
     pub fn new(
         action_type: Option<ActionType>,
         intensity_mode: Option<IntensityMode>,
@@ -89,7 +90,7 @@ impl TrackObject {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "snake_case")]
 pub struct TrueGearWsMessage {
     pub name: String,             // 消息名称
@@ -131,9 +132,23 @@ impl TrueGearWsMessageContainer {
     pub fn to_json(&self) -> String {
         serde_json::to_string(self).unwrap()
     }
+
+    pub(crate) fn from_json(json: &str) -> anyhow::Result<TrueGearWsMessageContainer> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    pub(crate) fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Decodes the base64 `Body` back into the message it carries.
+    pub(crate) fn decode_body(&self) -> anyhow::Result<TrueGearWsMessage> {
+        let body = BASE64_STANDARD.decode(&self.body)?;
+        Ok(serde_json::from_slice(&body)?)
+    }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "PascalCase")]
 pub struct Response {
     method: String,
@@ -141,13 +156,128 @@ pub struct Response {
 }
 
 impl Response {
+    /// Builds a response the way the TrueGear app sends it, with a base64 `Result`.
+    pub(crate) fn new(method: &str, result: &str) -> Response {
+        Response {
+            method: method.to_string(),
+            result: BASE64_STANDARD.encode(result),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
     pub(crate) fn from_message(m: Message) -> anyhow::Result<Response> {
         let message = m.to_text()?;
-        let r: Response = serde_json::from_str(&message)?;
+        let r: Response = serde_json::from_str(message)?;
         let new_result = BASE64_STANDARD.decode(r.result)?;
         Ok(Response {
             method: r.method,
             result: String::from_utf8(new_result).unwrap_or("".to_string()),
         })
     }
+
+    /// Sorts the answer by the shapes seen so far: the method echoed with a
+    /// `success` result for an accepted message, the `error` method with the
+    /// reason as result for a rejected one. Anything else is kept as is.
+    pub fn to_server_response(&self) -> ServerResponse {
+        let result = self.result.trim();
+        if self.method == "error" {
+            return ServerResponse::Error {
+                message: result.to_string(),
+            };
+        }
+        if result == "success" {
+            return ServerResponse::Ack {
+                method: self.method.clone(),
+            };
+        }
+        ServerResponse::Other {
+            method: self.method.clone(),
+            result: result.to_string(),
+        }
+    }
+}
+
+/// A response from the TrueGear app, decoded from `Response`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ServerResponse {
+    /// The app accepted a message sent with `method`.
+    Ack { method: String },
+    /// The app rejected a message.
+    Error { message: String },
+    /// An answer of a shape not seen yet, like battery or device status.
+    Other { method: String, result: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _frame(method: &str, result: &str) -> Message {
+        Message::text(format!(r#"{{"Method":"{}","Result":"{}"}}"#, method, result))
+    }
+
+    #[test]
+    fn decodes_the_base64_result() {
+        let response = Response::from_message(_frame("play_no_registered", "c3VjY2Vzcw=="))
+            .unwrap()
+            .to_server_response();
+        assert_eq!(
+            response,
+            ServerResponse::Ack {
+                method: "play_no_registered".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn decodes_an_error() {
+        let json = Response::new("error", "invalid body").to_json();
+        let response = Response::from_message(Message::text(json))
+            .unwrap()
+            .to_server_response();
+        assert_eq!(
+            response,
+            ServerResponse::Error {
+                message: "invalid body".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn round_trips_through_the_json_the_app_sends() {
+        let json = Response::new("play_no_registered", "anything at all\n").to_json();
+        let response = Response::from_message(Message::text(json))
+            .unwrap()
+            .to_server_response();
+        assert_eq!(
+            response,
+            ServerResponse::Other {
+                method: "play_no_registered".to_string(),
+                result: "anything at all".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_a_result_that_is_not_base64() {
+        assert!(Response::from_message(_frame("play_no_registered", "not base64!")).is_err());
+    }
+
+    #[test]
+    fn keeps_an_empty_result_when_it_is_not_utf8() {
+        // 0xff 0xfe in base64.
+        let response = Response::from_message(_frame("play_no_registered", "//4="))
+            .unwrap()
+            .to_server_response();
+        assert_eq!(
+            response,
+            ServerResponse::Other {
+                method: "play_no_registered".to_string(),
+                result: String::new(),
+            }
+        );
+    }
 }
@@ -0,0 +1,243 @@
+use crate::true_gear::def::{Response, TrackObject, TrueGearWsMessage, TrueGearWsMessageContainer};
+use futures_util::{SinkExt, StreamExt};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_tungstenite::tungstenite::Message;
+
+/// Messages kept for `recv`; newer ones are dropped when nobody reads them.
+const FEED_SIZE: usize = 64;
+
+/// Stand-in for the TrueGear app's WebSocket server. It decodes every
+/// `play_no_registered` frame, records the message, and answers with a
+/// `Response` frame so the driver can be exercised without the suit.
+pub(crate) struct MockServer {
+    addr: SocketAddr,
+    received: Arc<Mutex<Vec<TrueGearWsMessage>>>,
+    rx: mpsc::Receiver<TrueGearWsMessage>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    pub(crate) async fn bind(addr: &str) -> anyhow::Result<MockServer> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let (tx, rx) = mpsc::channel(FEED_SIZE);
+
+        let task = tokio::spawn(_accept_loop(listener, received.clone(), tx));
+        Ok(MockServer {
+            addr,
+            received,
+            rx,
+            task,
+        })
+    }
+
+    /// URL to hand to the client, with the same path as the real server.
+    pub(crate) fn url(&self) -> String {
+        format!("ws://{}/v1/tact/", self.addr)
+    }
+
+    /// Waits for the next message received by the server. Up to `FEED_SIZE`
+    /// unread messages are kept, later ones only show in `received_tracks`.
+    pub(crate) async fn recv(&mut self) -> Option<TrueGearWsMessage> {
+        self.rx.recv().await
+    }
+
+    /// Every track received so far, in arrival order.
+    pub(crate) fn received_tracks(&self) -> Vec<TrackObject> {
+        self.received
+            .lock()
+            .unwrap()
+            .iter()
+            .flat_map(|m| m.tracks.iter().cloned())
+            .collect()
+    }
+
+    pub(crate) fn received_messages(&self) -> usize {
+        self.received.lock().unwrap().len()
+    }
+}
+
+impl Drop for MockServer {
+    /// Stops listening and drops every open connection.
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn _accept_loop(
+    listener: TcpListener,
+    received: Arc<Mutex<Vec<TrueGearWsMessage>>>,
+    tx: mpsc::Sender<TrueGearWsMessage>,
+) {
+    // Owned here so aborting this task closes the connections too.
+    let mut connections = JoinSet::new();
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                while connections.try_join_next().is_some() {}
+                connections.spawn(_handle_connection(
+                    stream,
+                    peer,
+                    received.clone(),
+                    tx.clone(),
+                ));
+            }
+            Err(e) => eprintln!("[MockServer] accept failed: {}", e),
+        }
+    }
+}
+
+async fn _handle_connection(
+    stream: TcpStream,
+    peer: SocketAddr,
+    received: Arc<Mutex<Vec<TrueGearWsMessage>>>,
+    tx: mpsc::Sender<TrueGearWsMessage>,
+) {
+    let mut ws = match tokio_tungstenite::accept_async(stream).await {
+        Ok(ws) => ws,
+        Err(e) => {
+            eprintln!("[MockServer] handshake with {} failed: {}", peer, e);
+            return;
+        }
+    };
+
+    while let Some(Ok(item)) = ws.next().await {
+        let text = match item {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+        let response = match TrueGearWsMessageContainer::from_json(&text)
+            .and_then(|c| c.decode_body().map(|m| (c, m)))
+        {
+            Ok((container, message)) => {
+                received.lock().unwrap().push(message.clone());
+                let _ = tx.try_send(message);
+                Response::new(container.method(), "success")
+            }
+            Err(e) => Response::new("error", &e.to_string()),
+        };
+        if ws.send(Message::text(response.to_json())).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::true_gear::def::{IntensityMode, ServerResponse};
+    use crate::true_gear::{self, ConnectionState};
+    use std::time::Duration;
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    fn _track(intensity: i32) -> TrackObject {
+        TrackObject::new_shake_duration(
+            Some(60),
+            Some(intensity),
+            Some(intensity),
+            Some(IntensityMode::Const),
+            vec![0, 1, 100],
+        )
+    }
+
+    /// Next message of `server` with tracks, skipping the handshakes.
+    async fn _next_shake(server: &mut MockServer) -> TrueGearWsMessage {
+        tokio::time::timeout(TIMEOUT, async {
+            loop {
+                let message = server.recv().await.expect("server stopped");
+                if !message.tracks.is_empty() {
+                    return message;
+                }
+            }
+        })
+        .await
+        .expect("no shake received")
+    }
+
+    /// Waits until `client` noticed its server went away, which also lets the
+    /// aborted server release its port.
+    async fn _wait_disconnected(client: &true_gear::TrueGearClient) {
+        tokio::time::timeout(TIMEOUT, async {
+            while client.state() == ConnectionState::Connected {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the client didn't notice the server went away");
+    }
+
+    fn _json(tracks: &[TrackObject]) -> serde_json::Value {
+        serde_json::to_value(tracks).unwrap()
+    }
+
+    #[tokio::test]
+    async fn shakes_reach_the_server_after_the_handshake() {
+        let mut server = MockServer::bind("127.0.0.1:0").await.unwrap();
+        let mut client = true_gear::connect(&server.url()).await.unwrap();
+        let mut responses = client.subscribe();
+        client.wait_ready(TIMEOUT).await.unwrap();
+        // The handshake is an empty play, it is what the app answered.
+        let handshake = server.recv().await.unwrap();
+        assert!(handshake.tracks.is_empty());
+        assert_eq!(
+            responses.recv().await.unwrap(),
+            ServerResponse::Ack {
+                method: "play_no_registered".to_string(),
+            }
+        );
+
+        client.send_shake(vec![_track(40)]).await.unwrap();
+        _next_shake(&mut server).await;
+        assert_eq!(_json(&server.received_tracks()), _json(&[_track(40)]));
+        client.close().await.unwrap();
+        assert_eq!(client.state(), ConnectionState::Closed);
+    }
+
+    #[tokio::test]
+    async fn reconnects_when_the_server_comes_back() {
+        let server = MockServer::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.addr;
+        let mut client = true_gear::connect(&server.url()).await.unwrap();
+        client.wait_ready(TIMEOUT).await.unwrap();
+
+        drop(server);
+        _wait_disconnected(&client).await;
+
+        let mut server = MockServer::bind(&addr.to_string()).await.unwrap();
+        client.wait_ready(TIMEOUT).await.unwrap();
+        client.send_shake(vec![_track(70)]).await.unwrap();
+        let message = _next_shake(&mut server).await;
+        assert_eq!(_json(&message.tracks), _json(&[_track(70)]));
+        client.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn closes_while_reconnecting_to_a_silent_host() {
+        let server = MockServer::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.addr;
+        let mut client = true_gear::connect(&server.url()).await.unwrap();
+        client.wait_ready(TIMEOUT).await.unwrap();
+
+        drop(server);
+        _wait_disconnected(&client).await;
+        // Accepts the TCP connection but never answers the WebSocket upgrade.
+        let listener = TcpListener::bind(addr).await.unwrap();
+        let (_socket, _) = tokio::time::timeout(TIMEOUT, listener.accept())
+            .await
+            .expect("the client didn't try to reconnect")
+            .unwrap();
+
+        tokio::time::timeout(Duration::from_secs(1), client.close())
+            .await
+            .expect("close waited for the reconnection")
+            .unwrap();
+        assert_eq!(client.state(), ConnectionState::Closed);
+    }
+}
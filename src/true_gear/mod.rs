@@ -1,15 +1,56 @@
-use crate::true_gear::def::{IntensityMode, TrueGearWsMessage};
-use futures_util::stream::{SplitSink, SplitStream, StreamExt};
+use crate::true_gear::def::{IntensityMode, ServerResponse, TrueGearWsMessage};
+use futures_util::stream::{SplitStream, StreamExt};
 use futures_util::{SinkExt, TryStreamExt};
 use once_cell::sync::Lazy;
+use std::future::Future;
+use std::time::Duration;
 use tokio::net::TcpStream;
-use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio::select;
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::http::uri::InvalidUri;
+use tokio_tungstenite::tungstenite::http::{StatusCode, Uri};
 use tokio_tungstenite::tungstenite::Message;
 use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
 
 pub mod def;
+pub(crate) mod mock;
 
-static TRUE_GEAR_SERVER: &str = "ws://localhost:18233/v1/tact/";
+pub(crate) static TRUE_GEAR_SERVER: &str = "ws://localhost:18233/v1/tact/";
+static TRUE_GEAR_SERVER_PATH: &str = "/v1/tact/";
+
+/// Delay before the first reconnection attempt, doubled after every failure
+/// up to `RECONNECT_MAX_DELAY`.
+const RECONNECT_MIN_DELAY: Duration = Duration::from_millis(500);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(10);
+/// How long opening the WebSocket may take before the attempt is given up,
+/// instead of waiting minutes for the OS when the host is down.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Messages waiting to be written to the socket before new ones are dropped.
+const SEND_QUEUE_SIZE: usize = 32;
+/// Responses kept for subscribers that fall behind.
+const RESPONSE_QUEUE_SIZE: usize = 64;
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+enum Outgoing {
+    Text(String),
+    Close,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// A socket is being opened, or is open but the app hasn't answered the
+    /// handshake yet.
+    Connecting,
+    /// The app acknowledged the handshake on the current socket.
+    Connected,
+    /// The socket failed and the client is waiting to reconnect.
+    Degraded,
+    /// `close` was called.
+    Closed,
+}
 
 pub static TRUE_GEAR_SHAKE_AROUND_FRONT: Lazy<Vec<i32>> = Lazy::new(|| {
     vec![
@@ -95,28 +136,104 @@ pub static TRUE_GEAR_SHAKE_AROUND_BACK: Lazy<Vec<i32>> = Lazy::new(|| {
         ]
     });
 
+    pub static TRUE_GEAR_SHAKE_LEFT_FRONT: Lazy<Vec<i32>> = Lazy::new(|| {
+        vec![
+            0, 1, // line 1
+            4, 5, // line 2
+            8, 9, // line 3
+            12, 13, // line 4
+            16, 17, // line 5
+        ]
+    });
+
+    pub static TRUE_GEAR_SHAKE_LEFT_BACK: Lazy<Vec<i32>> = Lazy::new(|| {
+        vec![
+            100, 101, // line 1
+            104, 105, // line 2
+            108, 109, // line 3
+            112, 113, // line 4
+            116, 117, // line 5
+        ]
+    });
+
+    pub static TRUE_GEAR_SHAKE_RIGHT_FRONT: Lazy<Vec<i32>> = Lazy::new(|| {
+        vec![
+            2, 3, // line 1
+            6, 7, // line 2
+            10, 11, // line 3
+            14, 15, // line 4
+            18, 19, // line 5
+        ]
+    });
+
+    pub static TRUE_GEAR_SHAKE_RIGHT_BACK: Lazy<Vec<i32>> = Lazy::new(|| {
+        vec![
+            102, 103, // line 1
+            106, 107, // line 2
+            110, 111, // line 3
+            114, 115, // line 4
+            118, 119, // line 5
+        ]
+    });
+
 pub struct TrueGearClient {
-    writer: SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
-    is_connected: bool,
+    tx: mpsc::Sender<Outgoing>,
+    state: watch::Receiver<ConnectionState>,
+    responses: broadcast::Sender<ServerResponse>,
+    task: Option<JoinHandle<()>>,
 }
 
 impl TrueGearClient {
-    fn new(p0: SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>) -> TrueGearClient {
+    fn new(url: String, ws: WsStream) -> TrueGearClient {
+        let (tx, rx) = mpsc::channel(SEND_QUEUE_SIZE);
+        let (state_tx, state) = watch::channel(ConnectionState::Connecting);
+        let (responses, _) = broadcast::channel(RESPONSE_QUEUE_SIZE);
+        let task = tokio::spawn(_connection_loop(url, ws, rx, state_tx, responses.clone()));
         TrueGearClient {
-            writer: p0,
-            is_connected: false,
+            tx,
+            state,
+            responses,
+            task: Some(task),
+        }
+    }
+
+    /// Every response the app sends from now on, across reconnections.
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerResponse> {
+        self.responses.subscribe()
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        *self.state.borrow()
+    }
+
+    /// Waits until the app acknowledges the connection. Fails if the client
+    /// is closed or `timeout` elapses first.
+    pub async fn wait_ready(&self, timeout: Duration) -> anyhow::Result<()> {
+        let mut state = self.state.clone();
+        let ready = async move {
+            state
+                .wait_for(|s| matches!(s, ConnectionState::Connected | ConnectionState::Closed))
+                .await
+                .map(|s| *s)
+        };
+        match tokio::time::timeout(timeout, ready).await {
+            Ok(Ok(ConnectionState::Connected)) => Ok(()),
+            Ok(_) => Err(anyhow::anyhow!("TrueGear client was closed")),
+            Err(_) => Err(anyhow::anyhow!("TrueGear app did not answer within {:?}", timeout)),
         }
     }
 
     pub(crate) async fn close(&mut self) -> anyhow::Result<()> {
-        self.is_connected = false;
-        self.writer.close().await?;
+        let _ = self.tx.send(Outgoing::Close).await;
+        if let Some(task) = self.task.take() {
+            task.await?;
+        }
         Ok(())
     }
 
     pub(crate) async fn test_all(&mut self) -> anyhow::Result<()> {
-        let mut all_vec = Vec::from(TRUE_GEAR_SHAKE_MIDDLE_FRONT.clone());
-        all_vec.append(&mut Vec::from(TRUE_GEAR_SHAKE_MIDDLE_BACK.clone()));
+        let mut all_vec = TRUE_GEAR_SHAKE_MIDDLE_FRONT.clone();
+        all_vec.append(&mut TRUE_GEAR_SHAKE_MIDDLE_BACK.clone());
         let message =
             TrueGearWsMessage::new_no_registered(vec![def::TrackObject::new_shake_duration(
                 Some(100),
@@ -125,51 +242,214 @@ impl TrueGearClient {
                 Some(IntensityMode::Const),
                 all_vec,
             )]);
-        let message_data = def::TrueGearWsMessageContainer::new_no_registered(message).to_json();
-        self.writer.send(Message::text(message_data)).await?;
-        Ok(())
+        self.send_message(message).await
     }
 
     pub async fn send_shake(&mut self, p0: Vec<def::TrackObject>) -> anyhow::Result<()> {
-        let message = def::TrueGearWsMessageContainer::new_no_registered(
-            TrueGearWsMessage::new_no_registered(p0),
-        );
-        let message_data = message.to_json();
-        self.writer.send(Message::text(message_data)).await?;
-        Ok(())
+        self.send_message(TrueGearWsMessage::new_no_registered(p0)).await
+    }
+
+    /// Queues a message for the app. While disconnected, or when the app
+    /// can't keep up, the message is dropped: a late shake is worse than none.
+    pub async fn send_message(&mut self, p0: TrueGearWsMessage) -> anyhow::Result<()> {
+        match self.state() {
+            ConnectionState::Connecting | ConnectionState::Connected => {}
+            ConnectionState::Degraded => return Ok(()),
+            ConnectionState::Closed => return Err(anyhow::anyhow!("TrueGear client is closed")),
+        }
+        let message = def::TrueGearWsMessageContainer::new_no_registered(p0);
+        match self.tx.try_send(Outgoing::Text(message.to_json())) {
+            Ok(()) | Err(TrySendError::Full(_)) => Ok(()),
+            Err(TrySendError::Closed(_)) => Err(anyhow::anyhow!("TrueGear client is closed")),
+        }
+    }
+}
+
+/// Checks that `url` is a `ws://` or `wss://` URL with a host and a valid
+/// port, and fills in the app's `/v1/tact/` path when none is given.
+pub(crate) fn parse_server_url(url: &str) -> anyhow::Result<String> {
+    let invalid = |reason: &str| anyhow::anyhow!("Invalid TrueGear server URL '{}': {}", url, reason);
+    let uri: Uri = url.parse().map_err(|e: InvalidUri| invalid(&e.to_string()))?;
+    let scheme = match uri.scheme_str() {
+        Some(scheme @ ("ws" | "wss")) => scheme,
+        _ => return Err(invalid("it must start with ws:// or wss://")),
+    };
+    let authority = match uri.authority() {
+        Some(authority) if !authority.host().is_empty() => authority,
+        _ => return Err(invalid("missing host")),
+    };
+    // `Authority::port` silently returns `None` for out of range ports.
+    let host_port = authority.as_str().rsplit('@').next().unwrap_or_default();
+    let host_port = host_port.rsplit(']').next().unwrap_or_default();
+    if let Some((_, port)) = host_port.rsplit_once(':') {
+        // `ws://host:` is valid and means the scheme's default port.
+        if !port.is_empty() && port.parse::<u16>().is_err() {
+            return Err(invalid("port must be a number between 0 and 65535"));
+        }
     }
+    let path = match uri.path() {
+        "" | "/" => TRUE_GEAR_SERVER_PATH,
+        path => path,
+    };
+    let query = uri.query().map(|q| format!("?{}", q)).unwrap_or_default();
+    Ok(format!("{}://{}{}{}", scheme, authority, path, query))
 }
 
-pub(crate) async fn connect() -> anyhow::Result<TrueGearClient> {
-    let (c, r) = tokio_tungstenite::connect_async(TRUE_GEAR_SERVER).await?;
+pub(crate) async fn connect(url: &str) -> anyhow::Result<TrueGearClient> {
+    let url = parse_server_url(url)?;
+    let ws = _connect(&url).await?;
+    Ok(TrueGearClient::new(url, ws))
+}
+
+async fn _connect(url: &str) -> anyhow::Result<WsStream> {
+    let (c, r) = tokio::time::timeout(CONNECT_TIMEOUT, tokio_tungstenite::connect_async(url))
+        .await
+        .map_err(|_| anyhow::anyhow!("Timed out connecting to TrueGear at {}", url))?
+        .map_err(|e| anyhow::anyhow!("Failed to connect to TrueGear at {}: {}", url, e))?;
     if r.status() != StatusCode::SWITCHING_PROTOCOLS {
         println!("Failed to connect to True Gear: {}", r.status());
     }
-    let (w, r) = c.split();
+    Ok(c)
+}
+
+/// Owns the WebSocket for the lifetime of the client. When the app goes away
+/// it keeps reconnecting with exponential backoff until `close` is called.
+async fn _connection_loop(
+    url: String,
+    ws: WsStream,
+    mut rx: mpsc::Receiver<Outgoing>,
+    state: watch::Sender<ConnectionState>,
+    responses: broadcast::Sender<ServerResponse>,
+) {
+    let mut ws = Some(ws);
+    let mut delay = RECONNECT_MIN_DELAY;
+    loop {
+        if let Some(ws) = ws.take() {
+            let result = _run_connection(ws, &mut rx, &state, &responses).await;
+            match result {
+                Ok(true) => {
+                    _set_state(&state, ConnectionState::Closed);
+                    return;
+                }
+                Ok(false) => eprintln!("⚠️ TrueGear closed the connection"),
+                Err(e) => eprintln!("⚠️ Lost connection to TrueGear: {}", e),
+            }
+            _set_state(&state, ConnectionState::Degraded);
+            delay = RECONNECT_MIN_DELAY;
+        }
 
-    tokio::spawn(_listen_loop(r));
+        println!("Reconnecting to TrueGear in {:?} ...", delay);
+        let Some(()) = _unless_closed(tokio::time::sleep(delay), &mut rx).await else {
+            _set_state(&state, ConnectionState::Closed);
+            return;
+        };
 
-    let c = TrueGearClient::new(w);
-    Ok(c)
+        _set_state(&state, ConnectionState::Connecting);
+        let Some(result) = _unless_closed(_connect(&url), &mut rx).await else {
+            _set_state(&state, ConnectionState::Closed);
+            return;
+        };
+        match result {
+            Ok(new_ws) => ws = Some(new_ws),
+            Err(e) => {
+                eprintln!("⚠️ {}", e);
+                _set_state(&state, ConnectionState::Degraded);
+                delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+            }
+        }
+    }
+}
+
+/// Runs `future` while the client is disconnected, dropping the shakes
+/// queued meanwhile: they are stale by the time the connection is back.
+/// `None` if the client was closed first.
+async fn _unless_closed<F: Future>(
+    future: F,
+    rx: &mut mpsc::Receiver<Outgoing>,
+) -> Option<F::Output> {
+    tokio::pin!(future);
+    loop {
+        select! {
+            output = &mut future => return Some(output),
+            outgoing = rx.recv() => match outgoing {
+                Some(Outgoing::Text(_)) => {}
+                Some(Outgoing::Close) | None => return None,
+            },
+        }
+    }
+}
+
+/// Pumps one connection until it fails. Returns `Ok(true)` when it ended
+/// because the client was closed.
+async fn _run_connection(
+    ws: WsStream,
+    rx: &mut mpsc::Receiver<Outgoing>,
+    state: &watch::Sender<ConnectionState>,
+    responses: &broadcast::Sender<ServerResponse>,
+) -> anyhow::Result<bool> {
+    let (mut writer, reader) = ws.split();
+    // The app only answers messages, so an empty play acts as the handshake.
+    let handshake = def::TrueGearWsMessageContainer::new_no_registered(
+        TrueGearWsMessage::new_no_registered(Vec::new()),
+    );
+    writer.send(Message::text(handshake.to_json())).await?;
+
+    let listener = _listen_loop(reader, state, responses);
+    tokio::pin!(listener);
+    loop {
+        select! {
+            result = &mut listener => return result.map(|_| false),
+            outgoing = rx.recv() => match outgoing {
+                Some(Outgoing::Text(text)) => writer.send(Message::text(text)).await?,
+                Some(Outgoing::Close) | None => {
+                    let _ = writer.close().await;
+                    return Ok(true);
+                }
+            },
+        }
+    }
 }
 
 async fn _listen_loop(
-    mut r: SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+    mut r: SplitStream<WsStream>,
+    state: &watch::Sender<ConnectionState>,
+    responses: &broadcast::Sender<ServerResponse>,
 ) -> anyhow::Result<()> {
-    let mut has_connected = false;
     while let Some(item) = r.try_next().await? {
-        let message = def::Response::from_message(item);
-        if message.is_ok() {
-            if !has_connected {
-                has_connected = true;
-                println!("Connected!");
+        if !item.is_text() {
+            continue;
+        }
+        let response = match def::Response::from_message(item) {
+            Ok(response) => response.to_server_response(),
+            Err(e) => {
+                eprintln!("⚠️ Unreadable response from TrueGear: {}", e);
+                continue;
             }
-            // println!("[TrueGear] {:?}", message?);
+        };
+        _set_state(state, ConnectionState::Connected);
+        if let ServerResponse::Error { message } = &response {
+            eprintln!("⚠️ TrueGear error: {}", message);
         }
+        // No subscribers is fine, errors are still logged above.
+        let _ = responses.send(response);
     }
     Ok(())
 }
 
+fn _set_state(state: &watch::Sender<ConnectionState>, new_state: ConnectionState) {
+    let changed = state.send_if_modified(|s| {
+        if *s == new_state {
+            return false;
+        }
+        *s = new_state;
+        true
+    });
+    if changed {
+        println!("[TrueGear] {:?}", new_state);
+    }
+}
+
+#[allow(dead_code)]
 pub(crate) fn get_shake_level_index(p0: i32) -> Vec<i32> {
     match p0 {
         0 => vec![0, 1, 2, 3, 100, 101, 102, 103],
@@ -180,3 +460,49 @@ pub(crate) fn get_shake_level_index(p0: i32) -> Vec<i32> {
         _ => vec![],
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _parse(url: &str) -> String {
+        parse_server_url(url).unwrap()
+    }
+
+    #[test]
+    fn keeps_ws_and_wss_urls() {
+        assert_eq!(_parse("ws://localhost:18233/v1/tact/"), TRUE_GEAR_SERVER);
+        assert_eq!(_parse("wss://host:443/custom"), "wss://host:443/custom");
+        assert_eq!(_parse("ws://[::1]:18233/v1/tact/"), "ws://[::1]:18233/v1/tact/");
+    }
+
+    #[test]
+    fn fills_in_the_default_path() {
+        assert_eq!(_parse("ws://host:18233"), "ws://host:18233/v1/tact/");
+        assert_eq!(_parse("ws://host:18233/"), "ws://host:18233/v1/tact/");
+        assert_eq!(_parse("ws://host:18233?x=1"), "ws://host:18233/v1/tact/?x=1");
+        assert_eq!(_parse("ws://host:18233/?x=1"), "ws://host:18233/v1/tact/?x=1");
+        assert_eq!(_parse("ws://[::1]:18233"), "ws://[::1]:18233/v1/tact/");
+    }
+
+    #[test]
+    fn accepts_an_empty_port() {
+        assert_eq!(_parse("ws://host:"), "ws://host:/v1/tact/");
+    }
+
+    #[test]
+    fn rejects_bad_urls() {
+        for url in [
+            "localhost:18233",
+            "//localhost:18233",
+            "http://localhost:18233",
+            "ws://:18233",
+            "ws://",
+            "ws://host:99999",
+            "ws://[::1]:99999",
+            "ws://host:port",
+        ] {
+            assert!(parse_server_url(url).is_err(), "{} was accepted", url);
+        }
+    }
+}
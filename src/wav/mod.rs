@@ -0,0 +1,77 @@
+use hound::{SampleFormat, WavReader, WavSpec};
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::time::Duration;
+
+/// Decodes up to `limit` samples of a WAV stream into `f32` in the
+/// `-1.0..=1.0` range.
+fn _read_samples<R: Read>(reader: &mut WavReader<R>, limit: usize) -> anyhow::Result<Vec<f32>> {
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        SampleFormat::Int => {
+            let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .take(limit)
+                .map(|s| s.map(|s| s as f32 / scale))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+        SampleFormat::Float => reader
+            .samples::<f32>()
+            .take(limit)
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+    Ok(samples)
+}
+
+/// Reads a WAV file in fixed-duration blocks, the same way the live capture
+/// loop hands audio to the analysis.
+pub(crate) struct WavChunks {
+    reader: WavReader<BufReader<File>>,
+    spec: WavSpec,
+    chunk_len: usize,
+    position: Duration,
+    chunk_duration: Duration,
+}
+
+impl WavChunks {
+    pub(crate) fn open(path: &Path, chunk_duration: Duration) -> anyhow::Result<WavChunks> {
+        let reader = WavReader::open(path)
+            .map_err(|e| anyhow::anyhow!("Failed to open {}: {}", path.display(), e))?;
+        let spec = reader.spec();
+        let frames = (spec.sample_rate as u128 * chunk_duration.as_millis() / 1000).max(1);
+        Ok(WavChunks {
+            reader,
+            spec,
+            chunk_len: frames as usize * spec.channels as usize,
+            position: Duration::ZERO,
+            chunk_duration,
+        })
+    }
+
+    pub(crate) fn spec(&self) -> WavSpec {
+        self.spec
+    }
+
+    pub(crate) fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.reader.duration() as f64 / self.spec.sample_rate as f64)
+    }
+}
+
+impl Iterator for WavChunks {
+    /// Offset of the block from the start of the file, and its samples.
+    type Item = anyhow::Result<(Duration, Vec<f32>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match _read_samples(&mut self.reader, self.chunk_len) {
+            Ok(samples) if samples.is_empty() => None,
+            Ok(samples) => {
+                let offset = self.position;
+                self.position += self.chunk_duration;
+                Some(Ok((offset, samples)))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}